@@ -0,0 +1,81 @@
+use crate::IntoIter;
+use std::{
+    iter,
+    time::{Duration, Instant},
+};
+
+/// How [`AttemptTimeoutIter`] computes the timeout for each attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeoutMode {
+    /// Every attempt gets the full timeout. This is the default.
+    #[default]
+    Fixed,
+    /// The timeout is a total budget shared by every attempt. Each attempt
+    /// gets an equal share of whatever the earlier attempts and sleeps left.
+    RemainingBudget,
+    /// Each attempt gets an equal share of the time left until the deadline,
+    /// measured on the wall clock when the attempt starts. Slow attempts and
+    /// sleeps shrink the timeouts of later attempts, so the deadline is
+    /// honored. The timeout passed to the iterator is ignored.
+    Deadline(Instant),
+}
+
+/// An exponential backoff iterator which also yields the timeout for each
+/// attempt.
+///
+/// Each item is a pair of the delay to sleep after the attempt, and the
+/// timeout to apply to the attempt itself.
+#[derive(Debug, Clone)]
+pub struct AttemptTimeoutIter {
+    inner: IntoIter,
+    timeout: Duration,
+    mode: TimeoutMode,
+    remaining: Duration,
+}
+
+impl AttemptTimeoutIter {
+    pub(crate) fn new(inner: IntoIter, timeout: Duration) -> Self {
+        Self {
+            inner,
+            timeout,
+            mode: TimeoutMode::Fixed,
+            remaining: timeout,
+        }
+    }
+
+    /// Set how the timeout for each attempt is computed.
+    #[inline]
+    pub fn set_mode(&mut self, mode: TimeoutMode) {
+        self.mode = mode;
+    }
+}
+
+impl iter::Iterator for AttemptTimeoutIter {
+    type Item = (Option<Duration>, Duration);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let attempts_left = self.inner.len() as u32;
+        let duration = self.inner.next()?;
+        let timeout = match self.mode {
+            TimeoutMode::Fixed => self.timeout,
+            TimeoutMode::RemainingBudget => {
+                // Assume the attempt uses its whole timeout, then sleeps.
+                let timeout = self.remaining / attempts_left;
+                let spent = timeout.saturating_add(duration.unwrap_or_default());
+                self.remaining = self.remaining.saturating_sub(spent);
+                timeout
+            }
+            TimeoutMode::Deadline(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                remaining / attempts_left
+            }
+        };
+        Some((duration, timeout))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{IntoIter, JitterRng};
+use core::{iter, time::Duration};
+use fastrand::Rng;
+
+/// An exponential backoff iterator which ends once the delays would add up to
+/// more than the [total delay budget](crate::Backoff::set_max_total_delay).
+///
+/// It keeps a running sum of the jittered delays it yields. When adding the
+/// next delay would exceed the budget, that attempt becomes the final one and
+/// yields `None` instead. Since that depends on the jitter, the number of
+/// items isn't known up front, so unlike [`IntoIter`] this isn't an
+/// `ExactSizeIterator`.
+///
+/// Created by [`IntoIter::within_budget`].
+#[derive(Debug)]
+pub struct BudgetedIter<R = Rng> {
+    inner: IntoIter<R>,
+    budget: Option<Duration>,
+    elapsed: Duration,
+}
+
+impl Clone for BudgetedIter {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            budget: self.budget,
+            elapsed: self.elapsed,
+        }
+    }
+}
+
+impl<R: JitterRng> BudgetedIter<R> {
+    pub(crate) fn new(inner: IntoIter<R>) -> Self {
+        Self {
+            budget: inner.backoff().total_delay_budget,
+            inner,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Start the schedule over from the first attempt, with the whole budget
+    /// available again.
+    ///
+    /// See [`IntoIter::reset`].
+    #[inline]
+    pub fn reset(&mut self) {
+        self.inner.reset();
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// The most recent delay as it was before jitter was applied.
+    #[cfg(feature = "std")]
+    pub(crate) fn unjittered(&self) -> Option<Duration> {
+        self.inner.unjittered()
+    }
+}
+
+impl<R: JitterRng> iter::Iterator for BudgetedIter<R> {
+    type Item = Option<Duration>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (attempt, duration) = self.inner.next_quiet()?;
+        let Some(duration) = duration else {
+            return Some(None);
+        };
+        if let Some(budget) = self.budget {
+            let elapsed = self.elapsed.saturating_add(duration);
+            if elapsed > budget {
+                // Make this the final attempt. The retry callback is skipped,
+                // since the delay is never yielded.
+                self.inner.skip_to(u32::MAX);
+                return Some(None);
+            }
+            self.elapsed = elapsed;
+        }
+        self.inner.on_retry(attempt, duration);
+        Some(Some(duration))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // A budget may end the schedule early, but never before the next item.
+        let (low, high) = self.inner.size_hint();
+        match self.budget {
+            Some(_) => (low.min(1), high),
+            None => (low, high),
+        }
+    }
+}
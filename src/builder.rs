@@ -0,0 +1,111 @@
+use crate::{Backoff, BackoffError, GrowthMode};
+use core::time::Duration;
+
+/// A builder for a [`Backoff`], which validates the configuration once it's
+/// built rather than panicking in each setter.
+///
+/// Created by [`Backoff::builder`]. Settings which are left unset keep the
+/// values of [`Backoff::default`].
+///
+/// # Examples
+///
+/// ```rust
+/// use exponential_backoff::Backoff;
+/// use std::time::Duration;
+///
+/// let backoff = Backoff::builder()
+///     .attempts(5)
+///     .min(Duration::from_millis(50))
+///     .jitter(0.2)
+///     .build()?;
+/// assert_eq!(backoff.iter().count(), 5);
+/// # Ok::<(), exponential_backoff::BackoffError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackoffBuilder {
+    max_attempts: u32,
+    min: Duration,
+    max: Option<Duration>,
+    jitter: f32,
+    factor: u32,
+    growth_mode: GrowthMode,
+}
+
+impl BackoffBuilder {
+    /// Create a new instance with the default configuration.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            max_attempts: 3,
+            min: Duration::from_millis(100),
+            max: Some(Duration::from_secs(10)),
+            jitter: 0.3,
+            factor: 2,
+            growth_mode: GrowthMode::Exponential,
+        }
+    }
+
+    /// Set the max number of attempts.
+    #[inline]
+    pub fn attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Set the min duration.
+    #[inline]
+    pub fn min(mut self, min: Duration) -> Self {
+        self.min = min;
+        self
+    }
+
+    /// Set the max duration, or `None` for no max.
+    #[inline]
+    pub fn max(mut self, max: impl Into<Option<Duration>>) -> Self {
+        self.max = max.into();
+        self
+    }
+
+    /// Set the amount of jitter per backoff.
+    #[inline]
+    pub fn jitter(mut self, jitter: f32) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set the growth factor for each iteration of the backoff.
+    #[inline]
+    pub fn factor(mut self, factor: u32) -> Self {
+        self.factor = factor;
+        self
+    }
+
+    /// Set how the delays grow from one attempt to the next.
+    #[inline]
+    pub fn growth_mode(mut self, mode: GrowthMode) -> Self {
+        self.growth_mode = mode;
+        self
+    }
+
+    /// Validate the configuration and create the backoff.
+    ///
+    /// Returns an error if the jitter isn't between `0` and `1`, the min
+    /// duration is larger than the max duration, or the factor is `0`.
+    pub fn build(self) -> Result<Backoff, BackoffError> {
+        if self.factor == 0 {
+            return Err(BackoffError::FactorTooSmall { value: self.factor });
+        }
+        let mut backoff = Backoff::new(self.max_attempts, self.min, self.max);
+        backoff.try_set_jitter(self.jitter)?;
+        backoff.set_factor(self.factor);
+        backoff.set_growth_mode(self.growth_mode);
+        backoff.check_invariants()?;
+        Ok(backoff)
+    }
+}
+
+impl Default for BackoffBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
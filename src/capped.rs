@@ -0,0 +1,49 @@
+use crate::IntoIter;
+use core::{iter, time::Duration};
+
+/// A delay to sleep for, and whether it was cut short by the max duration.
+///
+/// Yielded by [`CappedIter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delay {
+    /// How long to sleep.
+    pub duration: Duration,
+    /// Whether the delay, jitter included, was longer than the max duration
+    /// and was clamped to it.
+    pub capped: bool,
+}
+
+/// An exponential backoff iterator which also yields whether each delay was
+/// clamped to the max duration.
+///
+/// The final attempt yields `None`, just like [`IntoIter`] does.
+///
+/// Created by [`IntoIter::with_capped`].
+#[derive(Debug, Clone)]
+pub struct CappedIter {
+    inner: IntoIter,
+}
+
+impl CappedIter {
+    pub(crate) fn new(inner: IntoIter) -> Self {
+        Self { inner }
+    }
+}
+
+impl iter::Iterator for CappedIter {
+    type Item = Option<Delay>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let duration = self.inner.next()?;
+        Some(duration.map(|duration| Delay {
+            duration,
+            capped: self.inner.is_capped(),
+        }))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,77 @@
+use crate::IntoIter;
+use alloc::sync::Arc;
+use core::{
+    iter,
+    sync::atomic::{AtomicU8, Ordering},
+    time::Duration,
+};
+
+/// An exponential backoff iterator which follows the state of a circuit
+/// breaker.
+///
+/// The state is read before every step:
+/// - [`CLOSED`](Self::CLOSED): the schedule is followed as normal.
+/// - [`OPEN`](Self::OPEN): retrying is pointless, so the next item is the
+///   final attempt and iteration ends after it.
+/// - [`HALF_OPEN`](Self::HALF_OPEN): the circuit is probing for recovery, so
+///   the base delay is halved. Jitter and clamping still apply afterwards, so
+///   the delay never drops below the floor.
+#[derive(Debug, Clone)]
+pub struct CircuitAwareIter {
+    inner: IntoIter,
+    state: Arc<AtomicU8>,
+    done: bool,
+}
+
+impl CircuitAwareIter {
+    /// The circuit is closed and requests flow normally.
+    pub const CLOSED: u8 = 0;
+    /// The circuit is open and requests are rejected.
+    pub const OPEN: u8 = 1;
+    /// The circuit is half-open and lets some requests through.
+    pub const HALF_OPEN: u8 = 2;
+
+    pub(crate) fn new(inner: IntoIter, state: Arc<AtomicU8>) -> Self {
+        Self {
+            inner,
+            state,
+            done: false,
+        }
+    }
+}
+
+impl iter::Iterator for CircuitAwareIter {
+    type Item = Option<Duration>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.state.load(Ordering::Acquire) {
+            Self::OPEN => {
+                self.done = true;
+                Some(None)
+            }
+            Self::HALF_OPEN => {
+                self.inner.set_halved(true);
+                let duration = self.inner.next();
+                self.inner.set_halved(false);
+                duration
+            }
+            _ => self.inner.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Opening the circuit may end the schedule early, but never before the
+        // next item.
+        if self.done {
+            return (0, Some(0));
+        }
+        let (low, high) = self.inner.size_hint();
+        (low.min(1), high)
+    }
+}
@@ -0,0 +1,45 @@
+use crate::Backoff;
+use retry::OperationResult;
+
+/// Run an operation written for the [`retry`](https://docs.rs/retry) crate,
+/// with this backoff providing the delays.
+///
+/// This makes it possible to swap out the schedule without rewriting retry
+/// call sites. The concepts map as follows:
+///
+/// - The delays passed to `retry::retry` are the `Some(duration)` items of
+///   the backoff iterator. The final `None` item has no counterpart, since the
+///   `retry` crate stops once its delays run out.
+/// - The number of tries reported in `retry::Error::tries` is at most the max
+///   number of attempts of the backoff, or one if the backoff allows none.
+/// - `OperationResult::Retry` sleeps and tries again, while
+///   `OperationResult::Err` returns straight away, just like in the `retry`
+///   crate. Plain `Result`s are retried on every error.
+/// - The [initial delay](Backoff::set_initial_delay) is slept once before the
+///   first try.
+///
+/// # Examples
+///
+/// ```rust
+/// use exponential_backoff::{retry_operation, Backoff};
+/// use retry::OperationResult;
+/// use std::{fs, io::ErrorKind, time::Duration};
+///
+/// let backoff = Backoff::new(3, Duration::from_millis(10), None);
+/// let result = retry_operation(&backoff, || match fs::read_to_string("README.md") {
+///     Ok(string) => OperationResult::Ok(string),
+///     Err(err) if err.kind() == ErrorKind::NotFound => OperationResult::Err(err),
+///     Err(err) => OperationResult::Retry(err),
+/// });
+/// assert!(result.is_ok());
+/// ```
+pub fn retry_operation<T, E, O, R>(backoff: &Backoff, operation: O) -> Result<T, retry::Error<E>>
+where
+    O: FnMut() -> R,
+    R: Into<OperationResult<T, E>>,
+{
+    if let Some(delay) = backoff.initial_delay {
+        std::thread::sleep(delay);
+    }
+    retry::retry(backoff.iter().flatten(), operation)
+}
@@ -0,0 +1,64 @@
+use crate::Backoff;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+/// A backoff schedule compiled down to its list of unjittered delays.
+///
+/// Created by [`Backoff::compile`](crate::Backoff::compile), or from an
+/// explicit list with [`from_schedule`](Self::from_schedule).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledBackoff {
+    delays: Vec<Duration>,
+}
+
+impl CompiledBackoff {
+    /// Create a new instance from the delays slept between attempts.
+    #[inline]
+    pub fn from_schedule(delays: impl IntoIterator<Item = Duration>) -> Self {
+        Self {
+            delays: delays.into_iter().collect(),
+        }
+    }
+
+    /// Get the delays slept between attempts.
+    #[inline]
+    pub fn delays(&self) -> &[Duration] {
+        &self.delays
+    }
+
+    /// Recover the parameters which generate this schedule.
+    ///
+    /// This only succeeds for a clean exponential schedule, where every delay
+    /// is a whole multiple of the one before it, optionally levelling off at
+    /// the last delay. The recovered backoff has no jitter, and its max is
+    /// the last delay. Returns `None` for any other list of delays.
+    pub fn to_backoff(&self) -> Option<Backoff> {
+        let (&first, &last) = (self.delays.first()?, self.delays.last()?);
+        let first_nanos = first.as_nanos();
+        if first_nanos == 0 {
+            return None;
+        }
+        let factor = match self.delays.get(1) {
+            Some(second) if second.as_nanos() % first_nanos == 0 => {
+                u32::try_from(second.as_nanos() / first_nanos).ok()?
+            }
+            Some(_) => return None,
+            None => 2,
+        };
+
+        // Every delay has to follow from the one before it.
+        let clean = self
+            .delays
+            .windows(2)
+            .all(|pair| pair[1] == pair[0].saturating_mul(factor).min(last));
+        if !clean || factor == 0 {
+            return None;
+        }
+
+        let attempts = u32::try_from(self.delays.len()).ok()?.checked_add(1)?;
+        let mut backoff = Backoff::new(attempts, first, last);
+        backoff.set_factor(factor);
+        backoff.set_jitter(0.0);
+        Some(backoff)
+    }
+}
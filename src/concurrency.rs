@@ -0,0 +1,60 @@
+use crate::IntoIter;
+use core::{iter, time::Duration};
+
+/// An exponential backoff iterator which also suggests how much concurrency
+/// to use for each attempt.
+///
+/// The suggested concurrency starts out at the configured value and is
+/// divided by the decay after every attempt, but never drops below `1`.
+///
+/// Created by [`IntoIter::with_concurrency`].
+#[derive(Debug, Clone)]
+pub struct ConcurrencyIter {
+    inner: IntoIter,
+    concurrency: u32,
+    decay: u32,
+}
+
+impl ConcurrencyIter {
+    pub(crate) fn new(inner: IntoIter, concurrency: u32) -> Self {
+        Self {
+            inner,
+            concurrency: concurrency.max(1),
+            decay: 2,
+        }
+    }
+
+    /// Set the concurrency suggested for the next attempt.
+    #[inline]
+    pub fn set_concurrency(&mut self, concurrency: u32) {
+        self.concurrency = concurrency.max(1);
+    }
+
+    /// Set what the concurrency is divided by after every attempt. Defaults
+    /// to `2`.
+    ///
+    /// ## Panics
+    /// This method panics if `0` is provided.
+    #[inline]
+    pub fn set_decay(&mut self, decay: u32) {
+        assert!(decay > 0, "<exponential-backoff>: decay must not be 0.");
+        self.decay = decay;
+    }
+}
+
+impl iter::Iterator for ConcurrencyIter {
+    type Item = (Option<Duration>, u32);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let duration = self.inner.next()?;
+        let concurrency = self.concurrency;
+        self.concurrency = (concurrency / self.decay).max(1);
+        Some((duration, concurrency))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
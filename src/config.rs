@@ -0,0 +1,46 @@
+use crate::{Backoff, BackoffError};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The serialized form of a [`Backoff`].
+///
+/// Durations are whole milliseconds, and a missing `max` means the delays are
+/// unbounded.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct BackoffConfig {
+    max_attempts: u32,
+    min: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max: Option<u64>,
+    jitter: f32,
+    factor: u32,
+}
+
+impl From<Backoff> for BackoffConfig {
+    fn from(backoff: Backoff) -> Self {
+        let millis = |duration: Duration| u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+        Self {
+            max_attempts: backoff.max_attempts,
+            min: millis(backoff.min),
+            max: (backoff.max != Duration::MAX).then(|| millis(backoff.max)),
+            jitter: backoff.jitter,
+            factor: backoff.factor,
+        }
+    }
+}
+
+impl TryFrom<BackoffConfig> for Backoff {
+    type Error = BackoffError;
+
+    fn try_from(config: BackoffConfig) -> Result<Self, Self::Error> {
+        let min = Duration::from_millis(config.min);
+        let max = config.max.map(Duration::from_millis);
+        if max.is_some_and(|max| min > max) {
+            return Err(BackoffError::MinGreaterThanMax);
+        }
+        let mut backoff = Backoff::new(config.max_attempts, min, max);
+        backoff.try_set_jitter(config.jitter)?;
+        backoff.set_factor(config.factor);
+        Ok(backoff)
+    }
+}
@@ -0,0 +1,66 @@
+use crate::IntoIter;
+use core::{iter, time::Duration};
+
+/// What to do next in a retry loop.
+///
+/// Yielded by [`ControlIter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    /// Make the first attempt straight away.
+    Try,
+    /// Sleep for the given delay, then make another attempt.
+    TryAfter(Duration),
+    /// Every attempt has been made, stop retrying.
+    Done,
+}
+
+/// An exponential backoff iterator which yields what to do next, rather than
+/// an optional delay.
+///
+/// The first item is [`Control::Try`], followed by a [`Control::TryAfter`] for
+/// every retry, and a single [`Control::Done`] once the attempts run out.
+///
+/// Created by [`Backoff::control`](crate::Backoff::control).
+#[derive(Debug, Clone)]
+pub struct ControlIter {
+    inner: IntoIter,
+    started: bool,
+    done: bool,
+}
+
+impl ControlIter {
+    pub(crate) fn new(inner: IntoIter) -> Self {
+        Self {
+            inner,
+            started: false,
+            done: false,
+        }
+    }
+}
+
+impl iter::Iterator for ControlIter {
+    type Item = Control;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if !self.started {
+            self.started = true;
+            if self.inner.size_hint().0 > 0 {
+                return Some(Control::Try);
+            }
+        }
+
+        // Every delay leads up to another attempt, while the final attempt
+        // has no delay and means we're done.
+        match self.inner.next().flatten() {
+            Some(duration) => Some(Control::TryAfter(duration)),
+            None => {
+                self.done = true;
+                Some(Control::Done)
+            }
+        }
+    }
+}
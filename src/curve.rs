@@ -0,0 +1,92 @@
+use core::{fmt::Debug, time::Duration};
+
+/// A growth curve for the delays between attempts.
+///
+/// The iterator asks the curve for the delay that follows each attempt, then
+/// applies jitter and clamps the result to `[min, max]`.
+pub trait Curve: Debug + Send + Sync {
+    /// Get the delay that follows the given zero-based attempt.
+    fn base_delay(&self, attempt: u32, min: Duration, max: Duration) -> Duration;
+
+    /// Whether the delay never shrinks from one attempt to the next.
+    ///
+    /// This lets long schedules be summed without asking for every delay.
+    /// Defaults to `false`.
+    #[inline]
+    fn is_monotonic(&self) -> bool {
+        false
+    }
+}
+
+/// Multiply the delay by a fixed factor on every attempt, starting at `min`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Exponential {
+    /// The growth factor.
+    pub factor: u32,
+}
+
+impl Curve for Exponential {
+    #[inline]
+    fn base_delay(&self, attempt: u32, min: Duration, _max: Duration) -> Duration {
+        // Multiply step by step rather than raising the factor to a power, so
+        // that it's the duration which saturates rather than the exponent. We
+        // can stop as soon as the duration stops changing.
+        let mut duration = min;
+        for _ in 0..attempt {
+            let next = duration.saturating_mul(self.factor);
+            if next == duration {
+                break;
+            }
+            duration = next;
+        }
+        duration
+    }
+
+    #[inline]
+    fn is_monotonic(&self) -> bool {
+        true
+    }
+}
+
+/// Add `min` to the delay on every attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Linear;
+
+impl Curve for Linear {
+    #[inline]
+    fn base_delay(&self, attempt: u32, min: Duration, _max: Duration) -> Duration {
+        min.saturating_mul(attempt.saturating_add(1))
+    }
+
+    #[inline]
+    fn is_monotonic(&self) -> bool {
+        true
+    }
+}
+
+/// Grow the delay along the Fibonacci sequence: `min`, `2 * min`, `3 * min`,
+/// `5 * min`, and so on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Fibonacci;
+
+impl Curve for Fibonacci {
+    #[inline]
+    fn base_delay(&self, attempt: u32, min: Duration, _max: Duration) -> Duration {
+        // Walk the sequence in durations, so it only saturates at
+        // `Duration::MAX`.
+        let (mut prev, mut current) = (min, min);
+        for _ in 0..attempt {
+            // Stop once saturated so huge attempt counts stay cheap.
+            if current.is_zero() || current == Duration::MAX {
+                break;
+            }
+            (prev, current) = (current, prev.saturating_add(current));
+        }
+        current
+    }
+
+    #[inline]
+    fn is_monotonic(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,53 @@
+use crate::IntoIter;
+use std::{iter, time::Instant};
+
+/// An exponential backoff iterator which yields the point in time of each
+/// retry, rather than how long to sleep before it.
+///
+/// Each deadline is the start plus the sum of the delays so far, so the
+/// delays are jittered just like those of [`IntoIter`]. The final attempt
+/// yields `None`. So does the first deadline too far in the future to be
+/// represented as an `Instant`, which ends the iterator early.
+///
+/// Created by [`Backoff::deadlines`](crate::Backoff::deadlines) or
+/// [`IntoIter::deadlines`].
+#[derive(Debug, Clone)]
+pub struct Deadlines {
+    inner: IntoIter,
+    deadline: Instant,
+}
+
+impl Deadlines {
+    pub(crate) fn new(inner: IntoIter, start: Instant) -> Self {
+        Self {
+            inner,
+            deadline: start,
+        }
+    }
+}
+
+impl iter::Iterator for Deadlines {
+    type Item = Option<Instant>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let Some(duration) = self.inner.next()? else {
+            return Some(None);
+        };
+        match self.deadline.checked_add(duration) {
+            Some(deadline) => {
+                self.deadline = deadline;
+                Some(Some(deadline))
+            }
+            None => {
+                self.inner.skip_to(u32::MAX);
+                Some(None)
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,92 @@
+use crate::IntoIter;
+use core::{iter, time::Duration};
+
+/// The state handed to the closure of [`Backoff::driven_by`](crate::Backoff::driven_by).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackoffState {
+    /// The zero-based attempt which just failed.
+    pub attempt: u32,
+    /// The delay chosen after the previous attempt, if any.
+    pub last_delay: Option<Duration>,
+    /// The total delay chosen so far.
+    pub elapsed: Duration,
+    /// The delay the backoff schedule would use after this attempt.
+    pub scheduled: Duration,
+}
+
+/// What to do after a failed attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackoffAction {
+    /// Sleep for the given delay, then retry.
+    Sleep(Duration),
+    /// Sleep for the scheduled delay, then retry.
+    Retry,
+    /// Stop retrying.
+    GiveUp,
+}
+
+/// An iterator whose delays are chosen by a closure.
+///
+/// Created by [`Backoff::driven_by`](crate::Backoff::driven_by).
+#[derive(Debug, Clone)]
+pub struct Driven<F> {
+    inner: IntoIter,
+    f: F,
+    attempt: u32,
+    last_delay: Option<Duration>,
+    elapsed: Duration,
+    done: bool,
+}
+
+impl<F> Driven<F> {
+    pub(crate) fn new(inner: IntoIter, f: F) -> Self {
+        Self {
+            inner,
+            f,
+            attempt: 0,
+            last_delay: None,
+            elapsed: Duration::ZERO,
+            done: false,
+        }
+    }
+}
+
+impl<F> iter::Iterator for Driven<F>
+where
+    F: FnMut(BackoffState) -> BackoffAction,
+{
+    type Item = Option<Duration>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        // The final attempt has no delay to choose.
+        let scheduled = match self.inner.next()? {
+            Some(scheduled) => scheduled,
+            None => return Some(None),
+        };
+
+        let state = BackoffState {
+            attempt: self.attempt,
+            last_delay: self.last_delay,
+            elapsed: self.elapsed,
+            scheduled,
+        };
+        let duration = match (self.f)(state) {
+            BackoffAction::Sleep(duration) => duration,
+            BackoffAction::Retry => scheduled,
+            BackoffAction::GiveUp => {
+                self.done = true;
+                return Some(None);
+            }
+        };
+
+        self.attempt += 1;
+        self.last_delay = Some(duration);
+        self.elapsed = self.elapsed.saturating_add(duration);
+        Some(Some(duration))
+    }
+}
@@ -0,0 +1,35 @@
+use crate::IntoIter;
+use core::{iter, time::Duration};
+
+/// An exponential backoff iterator which also yields the zero-based number of
+/// each attempt.
+///
+/// The final attempt still yields `None` as its delay.
+///
+/// Created by [`Backoff::iter_enumerated`](crate::Backoff::iter_enumerated).
+#[derive(Debug, Clone)]
+pub struct EnumeratedIter {
+    inner: IntoIter,
+}
+
+impl EnumeratedIter {
+    pub(crate) fn new(inner: IntoIter) -> Self {
+        Self { inner }
+    }
+}
+
+impl iter::Iterator for EnumeratedIter {
+    type Item = (u32, Option<Duration>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let attempt = self.inner.attempt();
+        let duration = self.inner.next()?;
+        Some((attempt, duration))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,66 @@
+use core::fmt;
+
+/// An invalid backoff configuration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum BackoffError {
+    /// The jitter wasn't between `0` and `1`.
+    JitterOutOfRange {
+        /// The jitter that was provided.
+        value: f32,
+    },
+    /// The encoded configuration didn't have the expected number of bytes.
+    InvalidLength {
+        /// The number of bytes that were expected.
+        expected: usize,
+        /// The number of bytes that were provided.
+        actual: usize,
+    },
+    /// The min duration was larger than the max duration.
+    MinGreaterThanMax,
+    /// The growth factor was smaller than `1`.
+    FactorTooSmall {
+        /// The factor that was provided.
+        value: u32,
+    },
+}
+
+impl fmt::Display for BackoffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::JitterOutOfRange { value } => {
+                write!(f, "jitter must be between 0 and 1, got {value}")
+            }
+            Self::InvalidLength { expected, actual } => {
+                write!(f, "expected {expected} bytes, got {actual}")
+            }
+            Self::MinGreaterThanMax => f.write_str("min duration is larger than max duration"),
+            Self::FactorTooSmall { value } => {
+                write!(f, "factor must be at least 1, got {value}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BackoffError {}
+
+/// A likely mistake in a backoff configuration which isn't invalid outright.
+///
+/// Returned by [`Backoff::check_invariants`](crate::Backoff::check_invariants).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Advisory {
+    /// Jitter is configured, but doesn't change any of the delays. This
+    /// happens when min and max are equal with strict bounds, or when the
+    /// delays are too small for the jitter to shift them.
+    IneffectiveJitter,
+}
+
+impl fmt::Display for Advisory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IneffectiveJitter => f.write_str("jitter will have no effect"),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! A C ABI for creating and driving backoff iterators.
+//!
+//! Handles are opaque pointers owned by the caller, which must release them
+//! with [`exponential_backoff_free`] exactly once.
+
+use crate::{Backoff, IntoIter};
+use std::{ptr, time::Duration};
+
+/// The iterator yielded a delay, which was written to the output.
+pub const EXPONENTIAL_BACKOFF_DELAY: i32 = 1;
+/// This is the final attempt, so there's no delay to sleep.
+pub const EXPONENTIAL_BACKOFF_LAST: i32 = 0;
+/// The attempts are exhausted, or an argument was null.
+pub const EXPONENTIAL_BACKOFF_DONE: i32 = -1;
+
+/// Create a new backoff iterator with the default jitter and factor.
+///
+/// A `max_nanos` of `u64::MAX` means the delays are unbounded. Returns null
+/// if `min_nanos` is larger than `max_nanos`.
+#[no_mangle]
+pub extern "C" fn exponential_backoff_new(
+    max_attempts: u32,
+    min_nanos: u64,
+    max_nanos: u64,
+) -> *mut IntoIter {
+    if min_nanos > max_nanos {
+        return ptr::null_mut();
+    }
+    let max = match max_nanos {
+        u64::MAX => None,
+        nanos => Some(Duration::from_nanos(nanos)),
+    };
+    let backoff = Backoff::new(max_attempts, Duration::from_nanos(min_nanos), max);
+    Box::into_raw(Box::new(backoff.into_iter()))
+}
+
+/// Advance the iterator, writing the delay in nanoseconds to `out_nanos`.
+///
+/// Returns [`EXPONENTIAL_BACKOFF_DELAY`] when a delay was written,
+/// [`EXPONENTIAL_BACKOFF_LAST`] on the final attempt, and
+/// [`EXPONENTIAL_BACKOFF_DONE`] once the attempts are exhausted or when either
+/// pointer is null. `out_nanos` is only written to when a delay is returned.
+///
+/// # Safety
+/// `handle` must be null or a live handle created by
+/// [`exponential_backoff_new`], which isn't used from another thread at the
+/// same time. `out_nanos` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn exponential_backoff_next(
+    handle: *mut IntoIter,
+    out_nanos: *mut u64,
+) -> i32 {
+    if handle.is_null() || out_nanos.is_null() {
+        return EXPONENTIAL_BACKOFF_DONE;
+    }
+    // SAFETY: the caller guarantees the handle is live and not aliased.
+    let iter = unsafe { &mut *handle };
+    match iter.next_nanos() {
+        Some(Some(nanos)) => {
+            // SAFETY: the caller guarantees the output is valid for writes.
+            unsafe { out_nanos.write(nanos) };
+            EXPONENTIAL_BACKOFF_DELAY
+        }
+        Some(None) => EXPONENTIAL_BACKOFF_LAST,
+        None => EXPONENTIAL_BACKOFF_DONE,
+    }
+}
+
+/// Free a handle created by [`exponential_backoff_new`]. Null is ignored.
+///
+/// # Safety
+/// `handle` must be null or a live handle created by
+/// [`exponential_backoff_new`]. It must not be used again afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn exponential_backoff_free(handle: *mut IntoIter) {
+    if !handle.is_null() {
+        // SAFETY: the caller guarantees the handle is live and owned by us.
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
@@ -1,62 +1,601 @@
-use super::Backoff;
+use super::{
+    Backoff, BudgetedIter, CappedIter, ConcurrencyIter, Floor, GrowthMode, JitterDirectionIter,
+    JitterRng, JitterStrategy, TakeWhileUnder,
+};
+#[cfg(feature = "std")]
+use super::{Deadlines, KeepaliveIter};
+use core::{iter, mem, time::Duration};
 use fastrand::Rng;
-use std::{iter, time::Duration};
+#[cfg(feature = "std")]
+use std::{thread, time::Instant};
 
 /// An exponential backoff iterator.
-#[derive(Debug, Clone)]
-pub struct IntoIter {
+///
+/// The jitter is drawn from `R`, see [`Backoff::iter_with_rng`]. Iterators
+/// using the default generator can be cloned. Cloning an iterator reseeds its
+/// random number generator. The clone carries on from the same attempt, but
+/// draws its own jitter, so that exploring alternatives with clones doesn't
+/// produce correlated delays. Without the `std` feature there's no entropy to
+/// reseed from, so the clone copies the generator and draws the same jitter
+/// as the original.
+#[derive(Debug)]
+pub struct IntoIter<R = Rng> {
     inner: Backoff,
-    rng: Rng,
+    rng: R,
     attempts: u32,
+    lifetime_attempts: u32,
+    previous: Option<Duration>,
+    base: Option<Duration>,
+    unjittered: Option<Duration>,
+    capped: bool,
+    halved: bool,
+    phase: Duration,
+    peeked: Option<(u32, Option<Option<Duration>>)>,
 }
 
-impl IntoIter {
-    pub(crate) fn new(inner: Backoff) -> Self {
+impl Clone for IntoIter {
+    fn clone(&self) -> Self {
         Self {
-            attempts: 0,
+            inner: self.inner.clone(),
+            #[cfg(feature = "std")]
             rng: Rng::new(),
+            #[cfg(not(feature = "std"))]
+            rng: self.rng.clone(),
+            attempts: self.attempts,
+            lifetime_attempts: self.lifetime_attempts,
+            previous: self.previous,
+            base: self.base,
+            unjittered: self.unjittered,
+            capped: self.capped,
+            halved: self.halved,
+            phase: self.phase,
+            peeked: self.peeked,
+        }
+    }
+}
+
+impl<R: JitterRng> IntoIter<R> {
+    pub(crate) fn new(inner: Backoff, rng: R) -> Self {
+        Self {
+            attempts: 0,
+            lifetime_attempts: 0,
+            rng,
+            previous: None,
+            base: None,
+            unjittered: None,
+            capped: false,
+            halved: false,
+            phase: Duration::ZERO,
+            peeked: None,
             inner,
         }
     }
+
+    /// Start the schedule over from the first attempt, e.g. after an
+    /// operation succeeded.
+    ///
+    /// This makes it possible to reuse one iterator across independent
+    /// operations. It also restarts an iterator which has run out. The random
+    /// number generator carries on where it was rather than being
+    /// reseeded, so a seeded iterator stays reproducible across resets.
+    ///
+    /// Attempts made before the reset still count towards the lifetime max,
+    /// see [`Backoff::set_lifetime_max_attempts`].
+    #[inline]
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+        self.previous = None;
+        self.base = None;
+        self.unjittered = None;
+        self.capped = false;
+        self.peeked = None;
+    }
+
+    /// Skip ahead to the first attempt whose delay is at the max duration.
+    ///
+    /// This is for when the caller already knows the dependency is down, and
+    /// walking through the short early delays would be pointless. The skipped
+    /// attempts still count towards the max number of attempts, so fewer
+    /// attempts remain afterwards. They don't count towards the lifetime max.
+    /// Does nothing if the schedule never reaches the max duration, or if the
+    /// iterator is already past that point.
+    #[inline]
+    pub fn skip_to_ceiling(&mut self) {
+        if let Some(attempt) = self.inner.ceiling_attempt() {
+            let attempt = attempt + u32::from(self.inner.first_retry_delay.is_some());
+            if attempt > self.attempt() {
+                self.attempts = attempt;
+                self.base = None;
+                self.peeked = None;
+            }
+        }
+    }
+
+    /// Jump to the given zero-based attempt, e.g. to resume a retry loop
+    /// after a restart.
+    ///
+    /// The next item is the one that follows `attempt`. Skipped attempts don't
+    /// draw any jitter, and don't count towards the lifetime max. Jumping to
+    /// the max attempts or beyond leaves the iterator exhausted.
+    ///
+    /// Since no jitter is drawn, the unjittered delay before `attempt` stands
+    /// in for the previous delay. This is an approximation for
+    /// [smoothing](Backoff::set_smoothing), [`Floor::Previous`] and
+    /// [`JitterStrategy::Decorrelated`], which look at the previous delay.
+    ///
+    /// [`Floor::Previous`]: crate::Floor::Previous
+    #[inline]
+    pub fn skip_to(&mut self, attempt: u32) {
+        self.attempts = attempt.min(self.inner.max_attempts);
+        self.previous = match (self.attempts, self.inner.first_retry_delay) {
+            (0, _) => None,
+            (1, Some(delay)) => Some(delay),
+            (attempts, first_retry) => {
+                let attempt = attempts - 1 - u32::from(first_retry.is_some());
+                Some(self.inner.clamped_delay(attempt))
+            }
+        };
+        self.base = None;
+        self.peeked = None;
+    }
+
+    /// The number of attempts remaining before the lifetime max is reached.
+    fn lifetime_remaining(&self) -> Option<u32> {
+        let max = self.inner.lifetime_max_attempts?;
+        Some(max.saturating_sub(self.lifetime_attempts))
+    }
+
+    /// Delay the next attempt by an extra `phase`, shifting every attempt
+    /// after it by the same amount.
+    #[cfg(feature = "std")]
+    pub(crate) fn set_phase(&mut self, phase: Duration) {
+        self.phase = phase;
+    }
+
+    /// Advance the iterator, yielding the delay as a number of nanoseconds.
+    ///
+    /// This is the same as `next`, for callers that do their own timing in
+    /// integer nanoseconds. Delays saturate at `u64::MAX` nanoseconds, which is
+    /// well over 500 years.
+    #[inline]
+    pub fn next_nanos(&mut self) -> Option<Option<u64>> {
+        let duration = self.next()?;
+        Some(duration.map(|duration| u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)))
+    }
+
+    /// Get the next item without advancing the iterator.
+    ///
+    /// The item is computed, jitter included, and kept until the next call to
+    /// `next`, which returns that same item. This makes it possible to show
+    /// when the next retry will happen before committing to it.
+    #[inline]
+    pub fn peek_next(&mut self) -> Option<Option<Duration>> {
+        if self.peeked.is_none() {
+            let attempts = self.attempts;
+            self.peeked = Some((attempts, self.advance()));
+        }
+        self.peeked.and_then(|(_, item)| item)
+    }
+
+    /// The number of attempts yielded so far in this run.
+    ///
+    /// A [peeked](Self::peek_next) item doesn't count until it's yielded.
+    #[inline]
+    pub fn attempt(&self) -> u32 {
+        match self.peeked {
+            Some((attempts, _)) => attempts,
+            None => self.attempts,
+        }
+    }
+
+    /// The number of attempts left before reaching the max attempts.
+    ///
+    /// The schedule may still end sooner because of the
+    /// [lifetime max](Backoff::set_lifetime_max_attempts).
+    #[inline]
+    pub fn remaining(&self) -> u32 {
+        self.inner.max_attempts.saturating_sub(self.attempt())
+    }
+
+    /// Whether the schedule has reached the max duration, so the delays won't
+    /// grow any further.
+    ///
+    /// This looks at the most recent delay before jitter was applied, and is
+    /// `false` until the first delay has been computed.
+    #[inline]
+    pub fn is_saturated(&self) -> bool {
+        self.base
+            .is_some_and(|base| self.inner.apply_delay_cap(base) >= self.inner.max)
+    }
+
+    /// Whether the most recent delay, jitter included, was longer than the
+    /// max duration and was clamped to it.
+    pub(crate) fn is_capped(&self) -> bool {
+        self.capped
+    }
+
+    /// Stop once the delays would add up to more than the
+    /// [total delay budget](Backoff::set_max_total_delay), if one is set.
+    #[inline]
+    pub fn within_budget(self) -> BudgetedIter<R> {
+        BudgetedIter::new(self)
+    }
+
+    /// Halve the base delay of the following items, before jitter and
+    /// clamping.
+    #[cfg(feature = "std")]
+    pub(crate) fn set_halved(&mut self, halved: bool) {
+        self.halved = halved;
+    }
+
+    /// Get the next item and its attempt, without running the retry callback.
+    ///
+    /// This is for adapters which may replace the item, and so must only run
+    /// the callback for delays they actually yield.
+    pub(crate) fn next_quiet(&mut self) -> Option<(u32, Option<Duration>)> {
+        let (attempt, item) = match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => (self.attempts, self.advance()),
+        };
+        item.map(|item| (attempt, item))
+    }
+
+    /// Run the retry callback for a delay which is about to be yielded.
+    pub(crate) fn on_retry(&self, attempt: u32, duration: Duration) {
+        if let Some(on_retry) = &self.inner.on_retry {
+            (on_retry.0)(attempt, duration);
+        }
+    }
+
+    /// The backoff this iterator walks.
+    pub(crate) fn backoff(&self) -> &Backoff {
+        &self.inner
+    }
+
+    /// The most recent delay as it was before jitter was applied.
+    pub(crate) fn unjittered(&self) -> Option<Duration> {
+        self.unjittered
+    }
 }
 
-impl iter::Iterator for IntoIter {
-    type Item = Option<Duration>;
+impl IntoIter {
+    /// Draw the jitter from a generator seeded with `seed`, making the
+    /// remaining delays reproducible.
+    ///
+    /// Note that cloning the iterator still reseeds the clone randomly.
+    #[inline]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::with_seed(seed);
+        self
+    }
 
+    /// Also suggest how much concurrency to use for each attempt, starting
+    /// at `concurrency` and halving after every attempt.
+    ///
+    /// See [`ConcurrencyIter`] for configuring how fast it decays.
     #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
+    pub fn with_concurrency(self, concurrency: u32) -> ConcurrencyIter {
+        ConcurrencyIter::new(self, concurrency)
+    }
+
+    /// Sleep through the rest of the schedule without running an operation,
+    /// and return how long was actually slept.
+    ///
+    /// This leaves the iterator itself untouched, and is meant for measuring
+    /// how the platform's timers behave rather than for retrying.
+    #[cfg(feature = "std")]
+    pub fn drain_sleeping(&self) -> Duration {
+        let start = Instant::now();
+        let mut iter = self.clone();
+        iter.inner.on_retry = None;
+        for duration in iter.flatten() {
+            thread::sleep(duration);
+        }
+        start.elapsed()
+    }
+
+    /// Yield the point in time of each retry, counting from `start`, rather
+    /// than the delays.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn deadlines(self, start: Instant) -> Deadlines {
+        Deadlines::new(self, start)
+    }
+
+    /// Also yield whether each delay was clamped to the max duration.
+    #[inline]
+    pub fn with_capped(self) -> CappedIter {
+        CappedIter::new(self)
+    }
+
+    /// Also yield whether jitter made each delay longer, shorter, or left it
+    /// the same.
+    #[inline]
+    pub fn with_jitter_direction(self) -> JitterDirectionIter {
+        JitterDirectionIter::new(self)
+    }
+
+    /// Sleep through each delay on the caller's behalf, firing `action` every
+    /// `interval` while sleeping.
+    ///
+    /// See [`KeepaliveIter`] for when the delays are slept.
+    ///
+    /// ## Panics
+    /// This method panics if `interval` is zero.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn with_keepalive<F: FnMut()>(self, interval: Duration, action: F) -> KeepaliveIter<F> {
+        KeepaliveIter::new(self, interval, action)
+    }
+
+    /// Stop iterating once a delay would exceed `limit`.
+    ///
+    /// Unlike lowering the max duration this doesn't cap the delay, it stops
+    /// retrying instead. The attempt whose delay would have exceeded the limit
+    /// becomes the final attempt, and yields `None` just like the last attempt
+    /// of a regular schedule.
+    #[inline]
+    pub fn take_while_under(self, limit: Duration) -> TakeWhileUnder {
+        TakeWhileUnder::new(self, limit)
+    }
+}
+
+impl<R: JitterRng> IntoIter<R> {
+    /// Compute the next item of the schedule.
+    #[inline]
+    fn advance(&mut self) -> Option<Option<Duration>> {
         // Check whether we've exceeded the number of attempts,
         // or whether we're on our last attempt. We don't want to sleep after
         // the last attempt.
-        if self.attempts == self.inner.max_attempts {
+        // The lifetime max applies across resets.
+        let lifetime_remaining = self.lifetime_remaining();
+        if self.attempts == self.inner.max_attempts || lifetime_remaining == Some(0) {
             return None;
-        } else if self.attempts == self.inner.max_attempts - 1 {
+        }
+        self.lifetime_attempts = self.lifetime_attempts.saturating_add(1);
+        if self.attempts == self.inner.max_attempts - 1 || lifetime_remaining == Some(1) {
             self.attempts = self.attempts.saturating_add(1);
             return Some(None);
         }
 
-        self.attempts = self.attempts.saturating_add(1);
+        // The first retry sleeps for exactly the given delay, and the curve
+        // starts from the next one.
+        let first_retry = self.inner.first_retry_delay;
+        if let (0, Some(delay)) = (self.attempts, first_retry) {
+            self.attempts = 1;
+            self.unjittered = Some(delay);
+            self.capped = false;
+            return Some(Some(delay));
+        }
+        let attempt = self.attempts - u32::from(first_retry.is_some());
 
-        // Create exponential duration.
-        let exponent = self.inner.factor.saturating_pow(self.attempts);
-        let duration = self.inner.min.saturating_mul(exponent);
-
-        // Apply jitter. Uses multiples of 100 to prevent relying on floats.
-        let jitter_factor = (self.inner.jitter * 100f32) as u32;
-        let random = self.rng.u32(0..jitter_factor * 2);
-        let mut duration = duration.saturating_mul(100);
-        if random < jitter_factor {
-            let jitter = duration.saturating_mul(random) / 100;
-            duration = duration.saturating_sub(jitter);
-        } else {
-            let jitter = duration.saturating_mul(random / 2) / 100;
-            duration = duration.saturating_add(jitter);
+        // Grow the previous base by the factor rather than computing it from
+        // scratch. Other curves don't grow that way, so ask them instead.
+        let exponential = self.inner.curve.is_none()
+            && self.inner.fractional_factor.is_none()
+            && self.inner.growth_mode == GrowthMode::Exponential;
+        let base = match self.base {
+            Some(base) if exponential => base.saturating_mul(self.inner.factor),
+            _ => self.inner.base_delay(attempt),
         };
-        duration /= 100;
+        self.base = Some(base);
 
-        // Make sure it doesn't exceed upper / lower bounds.
-        duration = duration.clamp(self.inner.min, self.inner.max);
+        // Apply the delay cap ahead of the jitter.
+        let mut duration = self.inner.apply_delay_cap(base);
+        if self.halved {
+            duration /= 2;
+        }
+        let floor = self.inner.floor(self.previous);
+        self.unjittered = Some(if self.inner.is_banded() {
+            self.inner.max
+        } else {
+            duration.clamp(floor, self.inner.max)
+        });
+        let (mut duration, capped) =
+            apply_strategy(&self.inner, duration, self.previous, floor, &mut self.rng);
+        self.capped = capped;
+        if let (Some(previous), true) = (self.previous, self.inner.smoothing < 1.0) {
+            let alpha = self.inner.smoothing as f64;
+            let smoothed = alpha * duration.as_secs_f64() + (1.0 - alpha) * previous.as_secs_f64();
+            duration = Duration::try_from_secs_f64(smoothed).unwrap_or(Duration::MAX);
+        }
+        self.attempts = self.attempts.saturating_add(1);
+        self.previous = Some(duration);
 
+        // The phase only shifts the schedule, so it's left out of `previous`.
+        let duration = duration.saturating_add(mem::take(&mut self.phase));
         Some(Some(duration))
     }
 }
+
+impl<R: JitterRng> iter::Iterator for IntoIter<R> {
+    type Item = Option<Duration>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (attempt, item) = self.next_quiet()?;
+        if let Some(duration) = item {
+            self.on_retry(attempt, duration);
+        }
+        Some(item)
+    }
+
+    /// Skip ahead in constant time, unless the skipped items affect what
+    /// follows: a lifetime max, a retry callback, a phase, or settings which
+    /// look at the previous delay.
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let stateful = self.inner.lifetime_max_attempts.is_some()
+            || self.inner.on_retry.is_some()
+            || self.inner.smoothing < 1.0
+            || self.inner.floor == Floor::Previous
+            || self.inner.jitter_strategy == JitterStrategy::Decorrelated
+            || !self.phase.is_zero();
+        if n > 0 && !stateful {
+            let n = u32::try_from(n).unwrap_or(u32::MAX);
+            self.skip_to(self.attempt().saturating_add(n));
+        } else {
+            for _ in 0..n {
+                self.next()?;
+            }
+        }
+        self.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // A peeked item has already been taken from the schedule.
+        let peeked = matches!(self.peeked, Some((_, Some(_)))) as u32;
+        let mut remaining = self.inner.max_attempts - self.attempts + peeked;
+        if let Some(lifetime_remaining) = self.lifetime_remaining() {
+            remaining = remaining.min(lifetime_remaining + peeked);
+        }
+        let remaining = remaining as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<R: JitterRng> iter::ExactSizeIterator for IntoIter<R> {}
+
+/// Apply the jitter strategy to an unjittered delay, and clamp it between
+/// `floor` and the max duration.
+///
+/// Also returns whether clamping to the max duration shortened the delay.
+pub(crate) fn apply_strategy(
+    backoff: &Backoff,
+    duration: Duration,
+    previous: Option<Duration>,
+    floor: Duration,
+    rng: &mut impl JitterRng,
+) -> (Duration, bool) {
+    let duration = match backoff.jitter_strategy {
+        JitterStrategy::Proportional => return apply_jitter(backoff, duration, floor, rng),
+        JitterStrategy::Full => random_between(rng, Duration::ZERO, duration),
+        JitterStrategy::Equal => {
+            let half = duration / 2;
+            half.saturating_add(random_between(rng, Duration::ZERO, duration - half))
+        }
+        JitterStrategy::Decorrelated => {
+            let previous = previous.unwrap_or(backoff.min);
+            random_between(rng, backoff.min, previous.saturating_mul(3))
+        }
+        JitterStrategy::FullDownward => random_between(rng, backoff.min, duration),
+        JitterStrategy::Absolute(spread) => random_between(
+            rng,
+            duration.saturating_sub(spread),
+            duration.saturating_add(spread),
+        ),
+    };
+    clamp(backoff, duration, floor)
+}
+
+/// Clamp a jittered delay between `floor` and the max duration, and report
+/// whether the max shortened it.
+fn clamp(backoff: &Backoff, duration: Duration, floor: Duration) -> (Duration, bool) {
+    (duration.clamp(floor, backoff.max), duration > backoff.max)
+}
+
+/// Pick a random duration in `[low, high]`, at nanosecond precision.
+fn random_between(rng: &mut impl JitterRng, low: Duration, high: Duration) -> Duration {
+    let nanos = |duration: Duration| u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+    let (low, high) = (nanos(low), nanos(high));
+    if low >= high {
+        return Duration::from_nanos(low);
+    }
+    Duration::from_nanos(rng.gen_range_u64(low..=high))
+}
+
+/// Apply jitter to an unjittered delay, and clamp it between `floor` and the
+/// max duration.
+fn apply_jitter(
+    backoff: &Backoff,
+    duration: Duration,
+    floor: Duration,
+    rng: &mut impl JitterRng,
+) -> (Duration, bool) {
+    // When min and max are the same, clamping after applying jitter would
+    // remove it entirely. So we clamp beforehand instead, and keep the jitter
+    // band around them.
+    let banded = backoff.is_banded();
+    let duration = if banded { backoff.max } else { duration };
+
+    // Apply jitter. Works in hundredths of a nanosecond to prevent relying
+    // on floats, which fits any duration once widened to `u128`.
+    let jitter_factor = (backoff.jitter * 100f32) as u32;
+    if jitter_factor == 0 {
+        return clamp(backoff, duration, floor);
+    }
+    // The bias moves the point at which we flip from shortening to
+    // lengthening the delay, without changing the size of the jitter.
+    let random = rng.gen_range(0..jitter_factor * 2);
+    let threshold = (jitter_factor as f32 * (1f32 - backoff.jitter_bias)) as u32;
+    let magnitude = if random < jitter_factor {
+        random
+    } else {
+        random / 2
+    };
+    let jitter = scaled_jitter(backoff, duration, magnitude, jitter_factor);
+    let nanos = duration.as_nanos() * 100;
+    let nanos = if random < threshold {
+        nanos.saturating_sub(jitter)
+    } else {
+        nanos.saturating_add(jitter)
+    };
+    let duration = from_nanos(nanos / 100);
+
+    // Make sure it doesn't exceed upper / lower bounds.
+    if banded {
+        return (duration, false);
+    }
+    clamp(backoff, duration, floor)
+}
+
+/// The largest amount jitter can shift `duration` by, rounded up to the next
+/// nanosecond.
+pub(crate) fn max_jitter(backoff: &Backoff, duration: Duration) -> Duration {
+    // Mirrors the math in `apply_jitter`, where the largest magnitude we can
+    // draw is one less than the jitter factor. Shortening a delay rounds the
+    // jitter up, so we do too.
+    let jitter_factor = (backoff.jitter * 100f32) as u32;
+    if jitter_factor == 0 {
+        return Duration::ZERO;
+    }
+    let magnitude = jitter_factor - 1;
+    let jitter = scaled_jitter(backoff, duration, magnitude, jitter_factor);
+    from_nanos(jitter.div_ceil(100))
+}
+
+/// The amount jitter shifts `duration` by for the given magnitude, in
+/// hundredths of a nanosecond.
+fn scaled_jitter(
+    backoff: &Backoff,
+    duration: Duration,
+    magnitude: u32,
+    jitter_factor: u32,
+) -> u128 {
+    match backoff.jitter_cap {
+        Some(cap) => {
+            let max_jitter = random_capped_jitter(duration, backoff.jitter, cap);
+            max_jitter.as_nanos() * 100 * magnitude as u128 / jitter_factor as u128
+        }
+        None => duration.as_nanos() * magnitude as u128,
+    }
+}
+
+/// Convert nanoseconds to a duration, saturating at `Duration::MAX`.
+fn from_nanos(nanos: u128) -> Duration {
+    const NANOS_PER_SEC: u128 = 1_000_000_000;
+    match u64::try_from(nanos / NANOS_PER_SEC) {
+        Ok(secs) => Duration::new(secs, (nanos % NANOS_PER_SEC) as u32),
+        Err(_) => Duration::MAX,
+    }
+}
+
+/// The largest shift jitter may apply to `base`: the proportional jitter, but
+/// never more than `cap`.
+fn random_capped_jitter(base: Duration, jitter_fraction: f32, cap: Duration) -> Duration {
+    // Works in whole percentages like `apply_jitter`, since a float can round
+    // a long delay past `Duration::MAX`.
+    let jitter_factor = (jitter_fraction * 100f32) as u128;
+    from_nanos(base.as_nanos() * jitter_factor / 100).min(cap)
+}
@@ -0,0 +1,39 @@
+use crate::IntoIter;
+use core::{cmp::Ordering, iter, time::Duration};
+
+/// An exponential backoff iterator which also yields whether jitter made each
+/// delay longer or shorter.
+///
+/// Each delay is compared to what it would have been without jitter. The
+/// final attempt has no delay, and yields [`Ordering::Equal`].
+///
+/// Created by [`IntoIter::with_jitter_direction`].
+#[derive(Debug, Clone)]
+pub struct JitterDirectionIter {
+    inner: IntoIter,
+}
+
+impl JitterDirectionIter {
+    pub(crate) fn new(inner: IntoIter) -> Self {
+        Self { inner }
+    }
+}
+
+impl iter::Iterator for JitterDirectionIter {
+    type Item = (Option<Duration>, Ordering);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let duration = self.inner.next()?;
+        let ordering = match (duration, self.inner.unjittered()) {
+            (Some(duration), Some(unjittered)) => duration.cmp(&unjittered),
+            _ => Ordering::Equal,
+        };
+        Some((duration, ordering))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,57 @@
+use core::ops::{Range, RangeInclusive};
+
+/// A source of randomness for jitter.
+///
+/// This is implemented for [`fastrand::Rng`], which is what iterators use by
+/// default. Implement it to draw jitter from another generator, e.g. one that
+/// doesn't need to seed itself from the environment, and pass it to
+/// [`Backoff::iter_with_rng`](crate::Backoff::iter_with_rng).
+pub trait JitterRng {
+    /// Draw a random number in `range`.
+    ///
+    /// The range is never empty.
+    fn gen_range(&mut self, range: Range<u32>) -> u32;
+
+    /// Draw a random number in `range`.
+    ///
+    /// The default implementation combines draws from
+    /// [`gen_range`](Self::gen_range). For ranges wider than a `u32` the
+    /// result is very slightly biased, so override it if the generator can
+    /// draw 64-bit numbers directly.
+    fn gen_range_u64(&mut self, range: RangeInclusive<u64>) -> u64 {
+        let (low, high) = range.into_inner();
+        let span = high - low;
+        if span < u32::MAX as u64 {
+            return low + self.gen_range(0..span as u32 + 1) as u64;
+        }
+        // Build 64 random bits out of 16-bit draws, and scale them to the span.
+        let bits = (0..4).fold(0u64, |bits, _| {
+            bits << 16 | self.gen_range(0..1 << 16) as u64
+        });
+        low + ((bits as u128 * (span as u128 + 1)) >> 64) as u64
+    }
+}
+
+impl JitterRng for fastrand::Rng {
+    #[inline]
+    fn gen_range(&mut self, range: Range<u32>) -> u32 {
+        self.u32(range)
+    }
+
+    #[inline]
+    fn gen_range_u64(&mut self, range: RangeInclusive<u64>) -> u64 {
+        self.u64(range)
+    }
+}
+
+impl<R: JitterRng + ?Sized> JitterRng for &mut R {
+    #[inline]
+    fn gen_range(&mut self, range: Range<u32>) -> u32 {
+        (**self).gen_range(range)
+    }
+
+    #[inline]
+    fn gen_range_u64(&mut self, range: RangeInclusive<u64>) -> u64 {
+        (**self).gen_range_u64(range)
+    }
+}
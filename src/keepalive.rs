@@ -0,0 +1,61 @@
+use crate::IntoIter;
+use std::{iter, mem, thread, time::Duration};
+
+/// An exponential backoff iterator which sleeps on the caller's behalf, and
+/// fires a keepalive action at a fixed interval while it does.
+///
+/// Each delay is yielded as usual, but is then slept through at the start of
+/// the following call to `next`, so callers must not sleep themselves. While
+/// sleeping the action fires after every full interval, which keeps
+/// connections from timing out during long delays.
+///
+/// Created by [`IntoIter::with_keepalive`].
+#[derive(Debug, Clone)]
+pub struct KeepaliveIter<F> {
+    inner: IntoIter,
+    interval: Duration,
+    action: F,
+    pending: Option<Duration>,
+}
+
+impl<F> KeepaliveIter<F> {
+    pub(crate) fn new(inner: IntoIter, interval: Duration, action: F) -> Self {
+        assert!(
+            interval > Duration::ZERO,
+            "<exponential-backoff>: keepalive interval must not be 0."
+        );
+        Self {
+            inner,
+            interval,
+            action,
+            pending: None,
+        }
+    }
+}
+
+impl<F> iter::Iterator for KeepaliveIter<F>
+where
+    F: FnMut(),
+{
+    type Item = Option<Duration>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut remaining = mem::take(&mut self.pending).unwrap_or_default();
+        while remaining >= self.interval {
+            thread::sleep(self.interval);
+            (self.action)();
+            remaining -= self.interval;
+        }
+        thread::sleep(remaining);
+
+        let duration = self.inner.next()?;
+        self.pending = duration;
+        Some(duration)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
@@ -37,21 +37,213 @@
 //! }
 //! # Ok(()) }
 //! ```
+//!
+//! # `no_std`
+//! Disabling the default `std` feature builds the crate on `core` and `alloc`.
+//! There's no entropy to seed the jitter from, so schedules are created with
+//! [`Backoff::iter_seeded`] or [`Backoff::iter_with_rng`] instead. Everything
+//! that sleeps, reads the clock, or seeds from the environment needs `std`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+#[cfg(feature = "std")]
+mod attempt_timeout;
+mod budgeted;
+mod builder;
+mod capped;
+#[cfg(feature = "std")]
+mod circuit;
+#[cfg(feature = "retry-compat")]
+mod compat;
+mod compiled;
+mod concurrency;
+#[cfg(feature = "serde")]
+mod config;
+#[cfg(feature = "std")]
+mod control;
+mod curve;
+#[cfg(feature = "std")]
+mod deadlines;
+#[cfg(feature = "std")]
+mod driven;
+#[cfg(feature = "std")]
+mod enumerated;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 mod into_iter;
+mod jitter_direction;
+mod jitter_rng;
+#[cfg(feature = "std")]
+mod keepalive;
+mod partial;
+#[cfg(feature = "std")]
+mod retry;
+mod retry_with;
+mod stats;
+#[cfg(feature = "stream")]
+mod stream;
+mod take_while_under;
 
-use std::time::Duration;
+use alloc::{borrow::ToOwned, format, string::String, sync::Arc, vec::Vec};
+#[cfg(feature = "std")]
+use core::sync::atomic::AtomicU8;
+use core::{fmt, iter, time::Duration};
+use fastrand::Rng;
+#[cfg(feature = "std")]
+use std::time::Instant;
 
+#[cfg(feature = "std")]
+pub use crate::attempt_timeout::{AttemptTimeoutIter, TimeoutMode};
+pub use crate::budgeted::BudgetedIter;
+pub use crate::builder::BackoffBuilder;
+pub use crate::capped::{CappedIter, Delay};
+#[cfg(feature = "std")]
+pub use crate::circuit::CircuitAwareIter;
+#[cfg(feature = "retry-compat")]
+pub use crate::compat::retry_operation;
+pub use crate::compiled::CompiledBackoff;
+pub use crate::concurrency::ConcurrencyIter;
+#[cfg(feature = "std")]
+pub use crate::control::{Control, ControlIter};
+pub use crate::curve::{Curve, Exponential, Fibonacci, Linear};
+#[cfg(feature = "std")]
+pub use crate::deadlines::Deadlines;
+#[cfg(feature = "std")]
+pub use crate::driven::{BackoffAction, BackoffState, Driven};
+#[cfg(feature = "std")]
+pub use crate::enumerated::EnumeratedIter;
+pub use crate::error::{Advisory, BackoffError};
 pub use crate::into_iter::IntoIter;
+pub use crate::jitter_direction::JitterDirectionIter;
+pub use crate::jitter_rng::JitterRng;
+#[cfg(feature = "std")]
+pub use crate::keepalive::KeepaliveIter;
+pub use crate::partial::PartialBackoff;
+#[cfg(feature = "std")]
+pub use crate::retry::{
+    retry, retry_ex, retry_while_healthy, retry_with_stats, AbortReason, RetryOptions, RetryOutcome,
+};
+#[cfg(feature = "std")]
+pub use crate::retry_with::retry_with;
+pub use crate::retry_with::retry_with_iter;
+pub use crate::stats::{JitterComparison, RetryStats, Violation};
+#[cfg(feature = "stream")]
+pub use crate::stream::BackoffStream;
+pub use crate::take_while_under::TakeWhileUnder;
+
+/// The lower bound applied to each jittered delay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Floor {
+    /// Never go below the min duration. This is the default.
+    #[default]
+    Min,
+    /// Never go below the previous delay, so the schedule never shrinks. The
+    /// first delay is floored at the min duration.
+    Previous,
+    /// Don't floor delays at all, so downward jitter may go below the min
+    /// duration.
+    None,
+}
+
+/// How the delays grow from one attempt to the next.
+///
+/// A [custom curve](Backoff::set_curve) takes precedence over this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GrowthMode {
+    /// Start at `min`, and multiply the delay by the factor on every attempt.
+    /// This is the default.
+    #[default]
+    Exponential,
+    /// Start at `min`, and add `min * factor` to the delay on every attempt,
+    /// so the nth delay is `min + min * factor * (n - 1)`.
+    Linear,
+    /// Wait `min` between every attempt.
+    Constant,
+}
+
+/// How jitter is applied to each delay.
+///
+/// The `Full`, `Equal` and `Decorrelated` strategies follow the AWS article
+/// ["Exponential Backoff And Jitter"](https://aws.amazon.com/blogs/architecture/exponential-backoff-and-jitter/).
+/// All strategies other than the default ignore the amount, bias and cap of
+/// the jitter. Every delay is still clamped between the
+/// [floor](Backoff::set_floor) and the max duration, so use [`Floor::None`]
+/// to get the article's exact formulas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JitterStrategy {
+    /// Shift the delay up or down by a random fraction of itself, see
+    /// [`Backoff::set_jitter`]. This is the default.
+    #[default]
+    Proportional,
+    /// Pick a random delay between zero and the delay.
+    Full,
+    /// Keep half of the delay, and pick the other half at random.
+    Equal,
+    /// Pick a random delay between the min duration and three times the
+    /// previous delay. This ignores the growth curve, and makes each delay
+    /// depend on the one before it.
+    Decorrelated,
+    /// Pick a random delay between the min duration and the delay, so jitter
+    /// only ever shortens a delay.
+    FullDownward,
+    /// Shift the delay up or down by a random amount of up to the given
+    /// duration, however long the delay is. Unlike proportional jitter, early
+    /// delays get as much jitter as later ones.
+    Absolute(Duration),
+}
 
 /// Exponential backoff type.
+///
+/// Iterators snapshot the configuration when they're created, including the
+/// state used by stateful modes such as [`Floor::Previous`]. Changing a
+/// backoff through its setters only affects iterators created afterwards.
+///
+/// With the `serde` feature this can be serialized. Only the max attempts,
+/// `min`, `max`, `jitter` and `factor` are stored, with durations as whole
+/// milliseconds, and `max` left out when the delays are unbounded.
+/// Deserializing validates the values, and rejects a jitter outside of `[0,
+/// 1]` or a min larger than the max.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "config::BackoffConfig", try_from = "config::BackoffConfig")
+)]
 pub struct Backoff {
     max_attempts: u32,
     min: Duration,
     max: Duration,
     jitter: f32,
+    jitter_bias: f32,
     factor: u32,
+    fractional_factor: Option<f64>,
+    growth_mode: GrowthMode,
+    curve: Option<Arc<dyn Curve>>,
+    delay_cap: Option<Duration>,
+    jitter_cap: Option<Duration>,
+    floor: Floor,
+    strict_bounds: bool,
+    lifetime_max_attempts: Option<u32>,
+    initial_delay: Option<Duration>,
+    first_retry_delay: Option<Duration>,
+    smoothing: f32,
+    jitter_strategy: JitterStrategy,
+    total_delay_budget: Option<Duration>,
+    name: Option<String>,
+    on_retry: Option<OnRetry>,
+}
+
+/// A callback run before each sleep, see [`Backoff::set_on_retry`].
+#[derive(Clone)]
+struct OnRetry(Arc<dyn Fn(u32, Duration) + Send + Sync>);
+
+impl fmt::Debug for OnRetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OnRetry(..)")
+    }
 }
 
 impl Backoff {
@@ -63,8 +255,128 @@ impl Backoff {
             min,
             max: max.into().unwrap_or(Duration::MAX),
             jitter: 0.3,
+            jitter_bias: 0.0,
             factor: 2,
+            fractional_factor: None,
+            growth_mode: GrowthMode::Exponential,
+            curve: None,
+            delay_cap: None,
+            jitter_cap: None,
+            floor: Floor::Min,
+            strict_bounds: false,
+            lifetime_max_attempts: None,
+            initial_delay: None,
+            first_retry_delay: None,
+            smoothing: 1.0,
+            jitter_strategy: JitterStrategy::Proportional,
+            total_delay_budget: None,
+            name: None,
+            on_retry: None,
+        }
+    }
+
+    /// Create a builder, which validates the configuration when it's built.
+    #[inline]
+    pub fn builder() -> BackoffBuilder {
+        BackoffBuilder::new()
+    }
+
+    /// Create a new instance which keeps the combined retry rate of `clients`
+    /// concurrent clients under `target_rps` requests per second.
+    ///
+    /// This models the worst case, where every client fails at the same moment
+    /// and each one retries after the shortest delay jitter allows. The rate is
+    /// the number of retries sent since that moment, divided by the time that
+    /// has passed. As long as the shortest delay is at least
+    /// `clients / target_rps` seconds, no client sends more than one retry per
+    /// `clients / target_rps` seconds, so the combined rate stays under the
+    /// target.
+    ///
+    /// Only `min` is picked. Later delays are never shorter than the first,
+    /// whatever the factor, so the factor is left at its default of 2 and only
+    /// spreads the retries out further. The policy makes 3 attempts, with the
+    /// default jitter.
+    ///
+    /// ## Panics
+    /// This method panics if `target_rps` isn't a positive, finite number.
+    pub fn for_target_rate(clients: u32, target_rps: f64) -> Self {
+        assert!(
+            target_rps > 0.0 && target_rps.is_finite(),
+            "<exponential-backoff>: target rate must be positive and finite."
+        );
+        let mut backoff = Self::new(3, Duration::ZERO, None);
+        let shortest = clients as f64 / target_rps / (1.0 - backoff.jitter as f64);
+        backoff.set_min(Duration::try_from_secs_f64(shortest).unwrap_or(Duration::MAX));
+        backoff
+    }
+
+    /// Create a new instance which waits roughly the same `delay` between
+    /// every attempt, spread out by `jitter`.
+    ///
+    /// ## Panics
+    /// This method panics if `jitter` is out of range, see
+    /// [`set_jitter`](Self::set_jitter).
+    #[inline]
+    pub fn jittered_constant(max_attempts: u32, delay: Duration, jitter: f32) -> Self {
+        let mut backoff = Self::new(max_attempts, delay, delay);
+        backoff.set_factor(1);
+        backoff.set_jitter(jitter);
+        backoff
+    }
+
+    /// Create a new instance matching the default retry configuration of
+    /// Google Cloud Tasks.
+    ///
+    /// This waits at least 100ms and at most an hour between attempts, and
+    /// doubles the delay up to 16 times.
+    #[inline]
+    pub fn new_gcp_cloud_tasks_default() -> Self {
+        Self::new_gcp_cloud_tasks_custom(Duration::from_millis(100), Duration::from_secs(3600), 16)
+    }
+
+    /// Create a new instance from a Google Cloud Tasks retry configuration.
+    ///
+    /// `min` and `max` map to the `minBackoff` and `maxBackoff` settings, and
+    /// the delay doubles `max_doublings` times.
+    ///
+    /// This makes `max_doublings + 2` attempts, one more than the
+    /// `max_doublings + 1` you might expect. The first retry waits for `min`
+    /// itself, and the final attempt has no delay after it, so one extra
+    /// attempt is needed for the delay to double `max_doublings` times.
+    #[inline]
+    pub fn new_gcp_cloud_tasks_custom(min: Duration, max: Duration, max_doublings: u32) -> Self {
+        Self::new(max_doublings.saturating_add(2), min, max)
+    }
+
+    /// Create a copy of this backoff with the settings of `overrides` applied.
+    ///
+    /// Settings which `overrides` leaves unset are inherited from this
+    /// backoff. This is useful for layered configuration, such as a global
+    /// default with per-service overrides.
+    pub fn merge(&self, overrides: &PartialBackoff) -> Backoff {
+        let mut backoff = self.clone();
+        if let Some(max_attempts) = overrides.max_attempts {
+            backoff.max_attempts = max_attempts;
+        }
+        if let Some(min) = overrides.min {
+            backoff.min = min;
+        }
+        if let Some(max) = overrides.max {
+            backoff.max = max;
+        }
+        if let Some(jitter) = overrides.jitter {
+            backoff.jitter = jitter;
+        }
+        if let Some(bias) = overrides.jitter_bias {
+            backoff.jitter_bias = bias;
+        }
+        if let Some(factor) = overrides.factor {
+            backoff.set_factor(factor);
         }
+        if let Some(floor) = overrides.floor {
+            backoff.floor = floor;
+        }
+        backoff
     }
 
     /// Set the min duration.
@@ -81,45 +393,1246 @@ impl Backoff {
 
     /// Set the amount of jitter per backoff.
     ///
+    /// Delays are clamped to `[min, max]` after jitter is applied. When min and
+    /// max are equal that would remove the jitter entirely, so in that case
+    /// delays are spread around them instead. See
+    /// [`set_strict_bounds`](Self::set_strict_bounds) to opt out of this.
+    ///
     /// ## Panics
     /// This method panics if a number smaller than `0` or larger than `1` is
-    /// provided.
+    /// provided, or if the jitter isn't finite. See
+    /// [`try_set_jitter`](Self::try_set_jitter) for a non-panicking version.
     #[inline]
     pub fn set_jitter(&mut self, jitter: f32) {
+        if self.try_set_jitter(jitter).is_err() {
+            panic!("<exponential-backoff>: jitter must be between 0 and 1.");
+        }
+    }
+
+    /// Set the amount of jitter per backoff, returning an error rather than
+    /// panicking if it's out of range.
+    ///
+    /// This is useful when the jitter comes from runtime configuration. `NaN`
+    /// and infinities are rejected too.
+    #[inline]
+    pub fn try_set_jitter(&mut self, jitter: f32) -> Result<(), BackoffError> {
+        if !jitter.is_finite() || !(0f32..=1f32).contains(&jitter) {
+            return Err(BackoffError::JitterOutOfRange { value: jitter });
+        }
+        self.jitter = jitter;
+        Ok(())
+    }
+
+    /// Always clamp delays to `[min, max]`, even when min and max are equal.
+    ///
+    /// By default an equal min and max keep the jitter around them, since
+    /// clamping would otherwise flatten every delay to exactly that value.
+    #[inline]
+    pub fn set_strict_bounds(&mut self, strict: bool) {
+        self.strict_bounds = strict;
+    }
+
+    /// Set the max number of attempts an iterator may make over its whole
+    /// lifetime, across every [`reset`](IntoIter::reset).
+    ///
+    /// Resetting an iterator after a success starts a fresh schedule, so a
+    /// dependency which keeps failing and recovering would otherwise be
+    /// retried forever. Once this many attempts have been made the iterator
+    /// gives up for good. Unlimited by default.
+    #[inline]
+    pub fn set_lifetime_max_attempts(&mut self, max_attempts: u32) {
+        self.lifetime_max_attempts = Some(max_attempts);
+    }
+
+    /// Stop retrying once the delays would add up to more than `budget`.
+    ///
+    /// The budget is applied by [`IntoIter::within_budget`] and by the retry
+    /// helpers such as [`retry_ex`]. They keep a running sum of the jittered
+    /// delays, and when adding the next delay would exceed the budget, that
+    /// attempt becomes the final one and yields `None` instead. This composes
+    /// with the max number of attempts, and whichever is hit first ends the
+    /// schedule. Plain iterators ignore the budget, so that their length is
+    /// known up front.
+    #[inline]
+    pub fn set_max_total_delay(&mut self, budget: Duration) {
+        self.total_delay_budget = Some(budget);
+    }
+
+    /// Get the budget set by [`set_max_total_delay`](Self::set_max_total_delay),
+    /// if any.
+    #[inline]
+    pub fn total_delay_budget(&self) -> Option<Duration> {
+        self.total_delay_budget
+    }
+
+    /// Sleep for `delay` before the very first attempt, e.g. to let a
+    /// dependency warm up.
+    ///
+    /// This is applied once by the retry helpers such as [`retry_ex`], and
+    /// doesn't change the delays between attempts. Iterators don't yield it.
+    #[inline]
+    pub fn set_initial_delay(&mut self, delay: Duration) {
+        self.initial_delay = Some(delay);
+    }
+
+    /// Sleep for exactly `delay` before the first retry, e.g. `Duration::ZERO`
+    /// to retry straight away.
+    ///
+    /// Unlike other delays this isn't clamped to `min` or jittered. The curve
+    /// then starts from the second retry, so that one sleeps for `min`.
+    /// Analytic helpers such as [`duration_at`](Self::duration_at) don't
+    /// account for it.
+    #[inline]
+    pub fn set_first_retry_delay(&mut self, delay: Duration) {
+        self.first_retry_delay = Some(delay);
+    }
+
+    /// Smooth out the jumps between delays with an exponential moving
+    /// average.
+    ///
+    /// Each delay is blended with the previous one as `alpha * delay + (1 -
+    /// alpha) * previous`, which produces a gentler ramp. An `alpha` of `1`
+    /// disables smoothing, and is the default. Values closer to `0` change
+    /// the delays more slowly.
+    ///
+    /// ## Panics
+    /// This method panics if a number smaller than `0` or larger than `1` is
+    /// provided.
+    #[inline]
+    pub fn set_smoothing(&mut self, alpha: f32) {
         assert!(
-            jitter > 0f32 && jitter < 1f32,
-            "<exponential-backoff>: jitter must be between 0 and 1."
+            (0f32..=1f32).contains(&alpha),
+            "<exponential-backoff>: smoothing must be between 0 and 1."
         );
-        self.jitter = jitter;
+        self.smoothing = alpha;
+    }
+
+    /// Set which direction the jitter leans towards.
+    ///
+    /// A bias of `0` shortens and lengthens delays equally often. Positive
+    /// values make longer delays more likely, up to `1` where jitter only ever
+    /// lengthens delays. Negative values do the same for shorter delays, down
+    /// to `-1`.
+    ///
+    /// ## Panics
+    /// This method panics if a number smaller than `-1` or larger than `1` is
+    /// provided.
+    #[inline]
+    pub fn set_jitter_bias(&mut self, bias: f32) {
+        assert!(
+            (-1f32..=1f32).contains(&bias),
+            "<exponential-backoff>: jitter bias must be between -1 and 1."
+        );
+        self.jitter_bias = bias;
+    }
+
+    /// Set how jitter is applied to each delay.
+    ///
+    /// Defaults to [`JitterStrategy::Proportional`].
+    #[inline]
+    pub fn set_jitter_strategy(&mut self, strategy: JitterStrategy) {
+        self.jitter_strategy = strategy;
+    }
+
+    /// Set the lower bound applied to each jittered delay.
+    ///
+    /// Defaults to [`Floor::Min`].
+    #[inline]
+    pub fn set_floor(&mut self, floor: Floor) {
+        self.floor = floor;
     }
 
     /// Set the growth factor for each iteration of the backoff.
     #[inline]
     pub fn set_factor(&mut self, factor: u32) {
         self.factor = factor;
+        self.fractional_factor = None;
+    }
+
+    /// Set a fractional growth factor, such as `1.5`, for each iteration of
+    /// the backoff.
+    ///
+    /// This replaces the integer factor until [`set_factor`](Self::set_factor)
+    /// is called again. Only the integer factor is stored by `to_bytes` and
+    /// the `serde` config.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the factor is negative or not finite.
+    #[inline]
+    pub fn set_factor_f64(&mut self, factor: f64) {
+        assert!(
+            factor.is_finite() && factor >= 0.0,
+            "<exponential-backoff>: factor must be a finite, non-negative number."
+        );
+        self.factor = factor as u32;
+        self.fractional_factor = Some(factor);
+    }
+
+    /// Set how the delays grow from one attempt to the next.
+    ///
+    /// Jitter and the `[min, max]` bounds are applied on top, whatever the
+    /// mode. Defaults to [`GrowthMode::Exponential`].
+    #[inline]
+    pub fn set_growth_mode(&mut self, mode: GrowthMode) {
+        self.growth_mode = mode;
+    }
+
+    /// Set a custom growth curve, replacing the exponential growth driven by
+    /// the factor.
+    ///
+    /// Jitter and the `[min, max]` bounds are still applied to the delays the
+    /// curve produces.
+    #[inline]
+    pub fn set_curve(&mut self, curve: impl Curve + 'static) {
+        self.curve = Some(Arc::new(curve));
+    }
+
+    /// Run `f` every time an iterator yields a delay to sleep for, e.g. to
+    /// count retries or log them.
+    ///
+    /// The callback receives the zero-based attempt that failed and the
+    /// jittered delay that follows it. It runs when the delay is yielded, so
+    /// [peeking](IntoIter::peek_next) doesn't trigger it, and it doesn't run
+    /// for the final attempt or for delays an adapter replaces with `None`.
+    /// Delays drawn internally, e.g. by
+    /// [`compare_jitter`](Self::compare_jitter), don't trigger it either.
+    /// Clones of this backoff and the iterators created
+    /// from it share the same callback, which is why it takes `Fn` rather than
+    /// `FnMut`; use atomics or a mutex to keep state.
+    #[inline]
+    pub fn set_on_retry(&mut self, f: impl Fn(u32, Duration) + Send + Sync + 'static) {
+        self.on_retry = Some(OnRetry(Arc::new(f)));
+    }
+
+    /// Cap the delay at `cap` before jitter is applied.
+    ///
+    /// By default jitter is applied to the raw delay, which is then clamped
+    /// to `[min, max]`. Once the schedule reaches `max`, upward jitter gets
+    /// clamped away while downward jitter doesn't, so delays bunch up at
+    /// `max`. With a delay cap the raw delay is first clamped to `[min, cap]`,
+    /// and jitter is then applied to the capped value so delays spread out
+    /// around `cap` instead. The final `[min, max]` clamp still applies, so
+    /// pick a `cap` below `max` to leave room for upward jitter.
+    #[inline]
+    pub fn with_delay_cap(mut self, cap: Duration) -> Self {
+        self.delay_cap = Some(cap);
+        self
+    }
+
+    /// Create a copy of this backoff with a different max duration.
+    ///
+    /// The ceiling can be raised as well as lowered, and everything else about
+    /// the schedule stays the same.
+    ///
+    /// ## Panics
+    /// This method panics if `new_max` is smaller than the min duration.
+    #[inline]
+    pub fn with_ceiling(&self, new_max: Duration) -> Backoff {
+        assert!(
+            new_max >= self.min,
+            "<exponential-backoff>: max must not be smaller than min."
+        );
+        let mut backoff = self.clone();
+        backoff.max = new_max;
+        backoff
+    }
+
+    /// Attach a name to this backoff, for keying and logging policies.
+    ///
+    /// The name is only a label. It doesn't affect the schedule, and is
+    /// ignored when comparing backoffs.
+    #[inline]
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    /// Get the name attached through [`with_name`](Self::with_name), if any.
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Create a copy of this backoff with the max number of attempts chosen so
+    /// that the 99th percentile of the total time spent sleeping is close to,
+    /// but not over, `target_p99`.
+    ///
+    /// This is an approximation: each jittered delay is modelled as spread
+    /// evenly across its jitter band, and their sum as normally distributed.
+    /// At least one attempt is always made.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn sized_for_p99(&self, target_p99: Duration) -> Backoff {
+        // The percentile only grows with the number of attempts, so we can
+        // binary search for the largest count that stays within the target.
+        const Z_SCORE_P99: f64 = 2.326;
+        let mut backoff = self.clone();
+        let (mut low, mut high) = (1, u32::MAX);
+        while low < high {
+            let attempts = low + (high - low).div_ceil(2);
+            backoff.max_attempts = attempts;
+            if backoff.percentile_total_delay(Z_SCORE_P99) <= target_p99 {
+                low = attempts;
+            } else {
+                high = attempts - 1;
+            }
+        }
+        backoff.max_attempts = low;
+        backoff
+    }
+
+    /// Multiply the max number of attempts by `n`, saturating at `u32::MAX`.
+    #[inline]
+    pub fn multiply_attempts(mut self, n: u32) -> Self {
+        self.max_attempts = self.max_attempts.saturating_mul(n);
+        self
+    }
+
+    /// Divide the max number of attempts by `n`, rounding down but keeping at
+    /// least one attempt.
+    ///
+    /// ## Panics
+    /// This method panics if `n` is `0`.
+    #[inline]
+    pub fn divide_attempts(mut self, n: u32) -> Self {
+        assert!(n > 0, "<exponential-backoff>: cannot divide attempts by 0.");
+        self.max_attempts = (self.max_attempts / n).max(1);
+        self
+    }
+
+    /// Get the delay that follows the given zero-based attempt, without
+    /// jitter.
+    ///
+    /// Returns `None` for the last attempt and anything past it, since no
+    /// sleep follows those.
+    #[inline]
+    pub fn duration_at(&self, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts.saturating_sub(1) {
+            return None;
+        }
+        Some(self.clamped_delay(attempt))
+    }
+
+    /// Get the total unjittered time spent sleeping between attempts.
+    ///
+    /// This is the sleep time, not the operation time: it sums the delays
+    /// that follow every attempt but the last, since no sleep follows the
+    /// final attempt. It's the same value as
+    /// [`expected_total_wait`](Self::expected_total_wait), named to make
+    /// clear that the time spent running the operation isn't included.
+    #[inline]
+    pub fn total_delay_excluding_last(&self) -> Duration {
+        self.expected_total_wait()
+    }
+
+    /// Get the total unjittered time spent sleeping before the schedule first
+    /// reaches the max duration.
+    ///
+    /// A retry run that is still going past this point is sleeping at the
+    /// ceiling, which makes it a useful alerting threshold. Returns
+    /// `Duration::ZERO` if the very first delay is already at the max, and
+    /// `Duration::MAX` if the schedule never reaches it.
+    #[inline]
+    pub fn time_to_ceiling(&self) -> Duration {
+        let mut total = Duration::ZERO;
+        for step in self.steps() {
+            if step.first >= self.max {
+                return total;
+            }
+            total = total.saturating_add(step.sum(|duration| duration));
+        }
+        Duration::MAX
+    }
+
+    /// Check the configuration for mistakes.
+    ///
+    /// Returns an error if the configuration is invalid. Otherwise returns
+    /// advisories for settings which are allowed, but are likely not what was
+    /// intended.
+    pub fn check_invariants(&self) -> Result<Vec<Advisory>, BackoffError> {
+        if self.min > self.max {
+            return Err(BackoffError::MinGreaterThanMax);
+        }
+        let mut advisories = Vec::new();
+        if self.jitter > 0f32 && !self.jitter_is_effective() {
+            advisories.push(Advisory::IneffectiveJitter);
+        }
+        Ok(advisories)
+    }
+
+    /// Check whether the jitter actually changes any delays.
+    ///
+    /// Jitter is computed in whole percentages of the delay, so a tiny jitter
+    /// or tiny delays can round it away entirely. This returns `false` when
+    /// even the smallest delay in the schedule can't be shifted by jitter.
+    #[inline]
+    pub fn jitter_is_effective(&self) -> bool {
+        if self.strict_bounds && self.min == self.max {
+            return false;
+        }
+        match self.steps().map(|step| step.first).min() {
+            Some(smallest) => into_iter::max_jitter(self, smallest) > Duration::ZERO,
+            None => false,
+        }
+    }
+
+    /// Check that this backoff never sleeps longer than `max_step` between
+    /// two attempts, nor longer than `max_total` in total, even if every delay
+    /// is pushed up by jitter as far as it can go.
+    ///
+    /// This is meant for tests which pin down the contract of a policy. Long
+    /// schedules are checked a run of similar delays at a time, so a plateau
+    /// or a slow ramp that breaks the per-step limit is only reported once,
+    /// for the first attempt that breaks it.
+    pub fn assert_within(
+        &self,
+        max_step: Duration,
+        max_total: Duration,
+    ) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        let mut attempt = 0;
+        let upper_bound = |attempt| self.jitter_bounds(self.clamped_delay(attempt)).1;
+        for step in self.steps() {
+            // The delays in a run only grow, so the last one breaks the limit
+            // if any does. Find the first one that does.
+            if self.jitter_bounds(step.last).1 > max_step {
+                let (mut low, mut high) = (attempt, attempt + step.count - 1);
+                while low < high {
+                    let mid = low + (high - low) / 2;
+                    if upper_bound(mid) > max_step {
+                        high = mid;
+                    } else {
+                        low = mid + 1;
+                    }
+                }
+                violations.push(Violation::Step {
+                    attempt: low,
+                    upper_bound: upper_bound(low),
+                });
+            }
+            attempt += step.count;
+        }
+
+        let upper_bound = self.sum_steps(|duration| self.jitter_bounds(duration).1);
+        if upper_bound > max_total {
+            violations.push(Violation::Total { upper_bound });
+        }
+
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(violations),
+        }
+    }
+
+    /// Compare the total time spent sleeping over `samples` jittered runs of
+    /// the schedule with the unjittered total.
+    ///
+    /// This quantifies how much jitter spreads out real retry runs. Every
+    /// sample walks the whole schedule.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn compare_jitter(&self, samples: usize) -> JitterComparison {
+        // The samples are never slept on, so the retry callback stays out.
+        let backoff = Backoff {
+            on_retry: None,
+            ..self.clone()
+        };
+        let totals = (0..samples).map(|_| {
+            backoff
+                .iter()
+                .flatten()
+                .fold(Duration::ZERO, Duration::saturating_add)
+        });
+        JitterComparison::new(self.expected_total_wait(), totals)
+    }
+
+    /// Draw a single jittered delay for the given zero-based attempt, using
+    /// the provided random number generator.
+    ///
+    /// This uses the same math as the iterator, which makes it possible to
+    /// test the distribution of a single step. Seeding the generator makes the
+    /// draw reproducible.
+    #[inline]
+    pub fn sample_step(&self, attempt: u32, rng: &mut impl JitterRng) -> Duration {
+        let duration = self.capped_delay(attempt);
+        into_iter::apply_strategy(self, duration, None, self.floor(None), rng).0
+    }
+
+    /// Draw a single jittered total of the time spent sleeping over the
+    /// whole schedule, using the provided random number generator.
+    ///
+    /// Every step is drawn with [`sample_step`](Self::sample_step), so this
+    /// models the spread of total retry times without running a retry loop.
+    /// It doesn't allocate, but does walk every attempt.
+    pub fn random_budget(&self, rng: &mut impl JitterRng) -> Duration {
+        (0..self.max_attempts.saturating_sub(1))
+            .map(|attempt| self.sample_step(attempt, rng))
+            .fold(Duration::ZERO, Duration::saturating_add)
+    }
+
+    /// Get the total time spent sleeping over the whole schedule, without
+    /// jitter.
+    ///
+    /// This doesn't walk every attempt, so it returns promptly even for
+    /// `u32::MAX` attempts. Custom curves can only be summed that way if they
+    /// are [monotonic](Curve::is_monotonic), and are otherwise walked step by
+    /// step. The sum saturates at `Duration::MAX`.
+    #[inline]
+    pub fn expected_total_wait(&self) -> Duration {
+        self.sum_steps(|duration| duration)
+    }
+
+    /// Get the longest time the whole schedule can spend sleeping, with every
+    /// delay jittered up as far as it goes.
+    ///
+    /// Like [`expected_total_wait`](Self::expected_total_wait) this doesn't
+    /// draw any random numbers, and saturates at `Duration::MAX`. A
+    /// [total delay budget](Self::set_max_total_delay) caps the result.
+    #[inline]
+    pub fn max_total_delay(&self) -> Duration {
+        let total = self.sum_steps(|duration| self.jitter_bounds(duration).1);
+        match self.total_delay_budget {
+            Some(budget) => total.min(budget),
+            None => total,
+        }
+    }
+
+    /// Get the shortest time the whole schedule can spend sleeping, with
+    /// every delay jittered down as far as it goes.
+    ///
+    /// This assumes the schedule runs to the end. A
+    /// [total delay budget](Self::set_max_total_delay) may end it sooner.
+    #[inline]
+    pub fn min_total_delay(&self) -> Duration {
+        self.sum_steps(|duration| self.jitter_bounds(duration).0)
+    }
+
+    /// Get the unjittered schedule as JSON values, for logging to structured
+    /// sinks.
+    ///
+    /// Each sleep becomes an object holding its zero-based `attempt`, the
+    /// `delay_ms` in milliseconds, and whether the delay was `capped` at the
+    /// max duration.
+    #[cfg(feature = "serde")]
+    pub fn schedule_json(&self) -> Vec<serde_json::Value> {
+        (0..self.max_attempts.saturating_sub(1))
+            .map(|attempt| {
+                let delay = self.capped_delay(attempt);
+                let capped = delay > self.max;
+                let delay = delay.clamp(self.min, self.max);
+                let delay_ms = u64::try_from(delay.as_millis()).unwrap_or(u64::MAX);
+                serde_json::json!({
+                    "attempt": attempt,
+                    "delay_ms": delay_ms,
+                    "capped": capped,
+                })
+            })
+            .collect()
+    }
+
+    /// Describe the policy in prose, for operators rather than engineers.
+    ///
+    /// For example: "Retries up to 3 times, waiting ~100ms, ~200ms, ~400ms
+    /// (±30% jitter), capped at 10s." Only the first few delays are listed,
+    /// followed by the final one.
+    pub fn describe(&self) -> String {
+        const LISTED: usize = 4;
+        let retries = self.max_attempts.saturating_sub(1);
+        if retries == 0 {
+            return String::from("Doesn't retry.");
+        }
+        let times = if retries == 1 { "time" } else { "times" };
+        let mut delays = Vec::new();
+        let mut listed = 0;
+        for step in self.steps().take(LISTED) {
+            // A run of growing delays can't be listed as one delay.
+            if step.first != step.last {
+                delays.push(format!("~{:?}", step.first));
+                listed += 1;
+                break;
+            }
+            delays.push(match step.count {
+                1 => format!("~{:?}", step.first),
+                count => format!("~{:?} (x{count})", step.first),
+            });
+            listed += step.count;
+        }
+        if listed < retries {
+            let last = self.clamped_delay(retries - 1);
+            delays.push(format!("…, then up to ~{last:?}"));
+        }
+        let mut description = format!(
+            "Retries up to {retries} {times}, waiting {}",
+            delays.join(", ")
+        );
+        // Round to the nearest percent. `f32::round` needs `std`, and the
+        // jitter is never negative.
+        let jitter = (self.jitter * 100f32 + 0.5) as u32;
+        if jitter > 0 {
+            description.push_str(&format!(" (±{jitter}% jitter)"));
+        }
+        if self.max != Duration::MAX {
+            description.push_str(&format!(", capped at {:?}", self.max));
+        }
+        description.push('.');
+        description
+    }
+
+    /// Check whether this backoff makes enough attempts to reach the target
+    /// availability, given the chance that any single attempt fails.
+    ///
+    /// Assuming attempts fail independently, the operation only fails when
+    /// every attempt does, so this checks whether
+    /// `1 - per_attempt_failure_rate ^ max_attempts >= target_availability`.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn is_aggressive_enough_for(
+        &self,
+        target_availability: f64,
+        per_attempt_failure_rate: f64,
+    ) -> bool {
+        let failure_rate = per_attempt_failure_rate.powf(self.max_attempts as f64);
+        1.0 - failure_rate >= target_availability
+    }
+
+    /// Get the number of attempts needed to reach the target confidence that
+    /// at least one attempt succeeds.
+    ///
+    /// Assuming attempts succeed independently, this is the smallest `n` for
+    /// which `1 - (1 - per_attempt_success) ^ n >= target`.
+    ///
+    /// ## Panics
+    /// This method panics if either argument isn't strictly between `0` and
+    /// `1`.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn attempts_for_confidence(&self, per_attempt_success: f64, target: f64) -> u32 {
+        assert!(
+            per_attempt_success > 0.0 && per_attempt_success < 1.0,
+            "<exponential-backoff>: per attempt success must be between 0 and 1."
+        );
+        assert!(
+            target > 0.0 && target < 1.0,
+            "<exponential-backoff>: target must be between 0 and 1."
+        );
+        let failure = 1.0 - per_attempt_success;
+        let attempts = ((1.0 - target).ln() / failure.ln()).ceil().max(1.0) as u32;
+        // Correct for rounding errors pushing us just past a whole number.
+        let confidence = |n: u32| 1.0 - failure.powf(n as f64);
+        if attempts > 1 && confidence(attempts - 1) >= target {
+            attempts - 1
+        } else {
+            attempts
+        }
+    }
+
+    /// The number of bytes [`to_bytes`](Self::to_bytes) encodes a backoff in.
+    pub const ENCODED_LEN: usize = 26;
+
+    /// Encode the configuration in a compact, fixed little-endian layout.
+    ///
+    /// The layout is the max attempts (`u32`), the min and max durations in
+    /// nanoseconds (`u64`), the jitter in permille (`u16`), and the factor
+    /// (`u32`). Durations saturate at `u64::MAX` nanoseconds, which decodes as
+    /// an unbounded max. Custom curves, caps and other settings aren't
+    /// encoded.
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        let nanos = |duration: Duration| u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        let jitter = (self.jitter * 1000f32 + 0.5) as u16;
+
+        let mut bytes = [0; Self::ENCODED_LEN];
+        bytes[0..4].copy_from_slice(&self.max_attempts.to_le_bytes());
+        bytes[4..12].copy_from_slice(&nanos(self.min).to_le_bytes());
+        bytes[12..20].copy_from_slice(&nanos(self.max).to_le_bytes());
+        bytes[20..22].copy_from_slice(&jitter.to_le_bytes());
+        bytes[22..26].copy_from_slice(&self.factor.to_le_bytes());
+        bytes
+    }
+
+    /// Decode a configuration encoded by [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BackoffError> {
+        let bytes: &[u8; Self::ENCODED_LEN] =
+            bytes.try_into().map_err(|_| BackoffError::InvalidLength {
+                expected: Self::ENCODED_LEN,
+                actual: bytes.len(),
+            })?;
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        let u64_at = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+
+        let min = Duration::from_nanos(u64_at(4));
+        let max = match u64_at(12) {
+            u64::MAX => Duration::MAX,
+            nanos => Duration::from_nanos(nanos),
+        };
+        if min > max {
+            return Err(BackoffError::MinGreaterThanMax);
+        }
+        let jitter = u16::from_le_bytes([bytes[20], bytes[21]]) as f32 / 1000f32;
+
+        let mut backoff = Self::new(u32_at(0), min, max);
+        backoff.try_set_jitter(jitter)?;
+        backoff.set_factor(u32_at(22));
+        Ok(backoff)
+    }
+
+    /// Compile the schedule down to its list of unjittered delays.
+    ///
+    /// This walks and stores every attempt.
+    pub fn compile(&self) -> CompiledBackoff {
+        let delays =
+            (0..self.max_attempts.saturating_sub(1)).map(|attempt| self.clamped_delay(attempt));
+        CompiledBackoff::from_schedule(delays)
     }
 
     /// Create an iterator.
+    ///
+    /// The iterator takes a snapshot of the configuration, so calling setters
+    /// on this backoff afterwards doesn't affect iterators that already exist.
     #[inline]
+    #[cfg(feature = "std")]
     pub fn iter(&self) -> IntoIter {
-        IntoIter::new(self.clone())
+        self.clone().into_iter()
+    }
+
+    /// Create an iterator for one worker out of `total_workers` racing for
+    /// the same resource.
+    ///
+    /// Each worker's first delay is shifted by `worker / total_workers` of the
+    /// first unjittered delay, which shifts all of its later attempts by the
+    /// same amount. Without jitter, the workers' first retries are spread
+    /// evenly across one interval, instead of all landing at once. Every
+    /// iterator also draws its jitter from its own random number generator,
+    /// so the workers don't jitter in lockstep either. The shift is added on
+    /// top of the jittered delay, so the first delay may exceed the max
+    /// duration by up to one interval.
+    ///
+    /// ## Panics
+    /// This method panics if `total_workers` is `0`, or `worker` isn't smaller
+    /// than `total_workers`.
+    #[cfg(feature = "std")]
+    pub fn partition(&self, worker: u32, total_workers: u32) -> IntoIter {
+        assert!(
+            worker < total_workers,
+            "<exponential-backoff>: worker must be smaller than total_workers."
+        );
+        self.iter_with_phase(worker as f64 / total_workers as f64)
+    }
+
+    /// Create a cohort of `n` iterators, as if for `n` workers racing for the
+    /// same resource.
+    ///
+    /// Iterator `i` is the same as [`partition(i, n)`](Self::partition): its
+    /// schedule is shifted by `i / n` of the first interval, and it draws its
+    /// jitter independently from the others.
+    #[cfg(feature = "std")]
+    pub fn fan_out(&self, n: usize) -> Vec<IntoIter> {
+        (0..n)
+            .map(|i| self.iter_with_phase(i as f64 / n as f64))
+            .collect()
+    }
+
+    /// Create an iterator whose schedule is shifted by the given fraction of
+    /// the first unjittered delay.
+    #[cfg(feature = "std")]
+    fn iter_with_phase(&self, fraction: f64) -> IntoIter {
+        let interval = self.clamped_delay(0);
+        let mut iter = self.iter();
+        iter.set_phase(interval.mul_f64(fraction));
+        iter
+    }
+
+    /// Create a stream which sleeps between attempts using `sleeper`.
+    ///
+    /// The sleeper returns a future which completes after the given delay,
+    /// such as `tokio::time::sleep`. See [`BackoffStream`] for when the
+    /// stream yields.
+    #[cfg(feature = "stream")]
+    pub fn stream_with<F, Fut>(&self, sleeper: F) -> BackoffStream
+    where
+        F: FnMut(Duration) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        BackoffStream::new(self.iter(), sleeper)
+    }
+
+    /// Create a stream which sleeps between attempts using
+    /// `tokio::time::sleep`.
+    ///
+    /// See [`BackoffStream`] for when the stream yields.
+    #[cfg(feature = "tokio")]
+    pub fn stream(&self) -> BackoffStream {
+        self.stream_with(tokio::time::sleep)
+    }
+
+    /// Create an iterator whose jitter is drawn from a generator seeded with
+    /// `seed`, making its delays reproducible.
+    ///
+    /// The jitter math is the same as for [`iter`](Self::iter), only the
+    /// source of randomness differs. This is useful for snapshot testing
+    /// retry timing without disabling jitter.
+    #[inline]
+    pub fn iter_seeded(&self, seed: u64) -> IntoIter {
+        IntoIter::new(self.clone(), Rng::with_seed(seed))
+    }
+
+    /// Create an iterator which draws its jitter from `rng`.
+    ///
+    /// This is for environments where the default generator can't seed
+    /// itself, or where the jitter must come from a particular source. The
+    /// adapters on [`IntoIter`] need the default generator, see
+    /// [`iter_seeded`](Self::iter_seeded).
+    #[inline]
+    pub fn iter_with_rng<R: JitterRng>(&self, rng: R) -> IntoIter<R> {
+        IntoIter::new(self.clone(), rng)
+    }
+
+    /// Create an iterator which also yields the zero-based number of each
+    /// attempt, e.g. for structured logging.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn iter_enumerated(&self) -> EnumeratedIter {
+        EnumeratedIter::new(self.iter())
+    }
+
+    /// Create an iterator which yields the point in time of each retry,
+    /// counting from `start`.
+    ///
+    /// This suits event loops which schedule retries on a timer, rather than
+    /// sleeping in between.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn deadlines(&self, start: Instant) -> Deadlines {
+        Deadlines::new(self.iter(), start)
+    }
+
+    /// Create an iterator which yields what to do next in a retry loop.
+    ///
+    /// Unlike the `Option<Duration>` items of [`iter`](Self::iter), there's no
+    /// way to accidentally sleep after the last attempt.
+    ///
+    /// # Examples
+    ///
+    /// A loop over [`iter`](Self::iter) migrates as follows:
+    ///
+    /// ```rust
+    /// # fn retry() -> std::io::Result<()> {
+    /// use exponential_backoff::{Backoff, Control};
+    /// use std::{fs, thread, time::Duration};
+    ///
+    /// let backoff = Backoff::new(3, Duration::from_millis(100), None);
+    /// let mut last_error = None;
+    /// for step in backoff.control() {
+    ///     match step {
+    ///         Control::Try => {}
+    ///         Control::TryAfter(duration) => thread::sleep(duration),
+    ///         Control::Done => break,
+    ///     }
+    ///     match fs::read_to_string("README.md") {
+    ///         Ok(s) => {
+    ///             println!("{}", s);
+    ///             return Ok(());
+    ///         }
+    ///         Err(err) => last_error = Some(err),
+    ///     }
+    /// }
+    /// # if let Some(err) = last_error { return Err(err) }
+    /// # Ok(()) }
+    /// # retry().unwrap();
+    /// ```
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn control(&self) -> ControlIter {
+        ControlIter::new(self.iter())
+    }
+
+    /// Create an iterator that makes at most `n` attempts.
+    ///
+    /// Unlike calling `take(n)` on the iterator, the last attempt always
+    /// yields `None`, so callers still get the signal not to sleep after it.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn into_iter_capped_attempts(self, n: u32) -> impl Iterator<Item = Option<Duration>> {
+        let mut iter = self.into_iter();
+        let mut remaining = n;
+        iter::from_fn(move || {
+            remaining = remaining.checked_sub(1)?;
+            let (attempt, duration) = iter.next_quiet()?;
+            // The last delay is replaced, so the retry callback skips it.
+            if remaining == 0 {
+                return Some(None);
+            }
+            if let Some(duration) = duration {
+                iter.on_retry(attempt, duration);
+            }
+            Some(duration)
+        })
+    }
+
+    /// Create an iterator which never shifts a delay by more than `cap`.
+    ///
+    /// Jitter is normally proportional to the delay, so a 30% jitter on a
+    /// 10 second delay can move it by up to 3 seconds. With a cap the largest
+    /// possible shift becomes the smaller of the two, which keeps large
+    /// delays steady while still spreading out small ones.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn into_iter_with_random_capped_jitter(mut self, cap: Duration) -> IntoIter {
+        self.jitter_cap = Some(cap);
+        self.into_iter()
+    }
+
+    /// Create an iterator which follows the state of a circuit breaker.
+    ///
+    /// See [`CircuitAwareIter`] for how each state affects the schedule.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn into_iter_with_circuit_state(self, state: Arc<AtomicU8>) -> CircuitAwareIter {
+        CircuitAwareIter::new(self.into_iter(), state)
+    }
+
+    /// Create an iterator which also yields the timeout for each attempt.
+    ///
+    /// By default every attempt gets the full `timeout`. See
+    /// [`TimeoutMode`] for sharing it between attempts as a budget instead.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn into_iter_with_attempt_timeout(self, timeout: Duration) -> AttemptTimeoutIter {
+        AttemptTimeoutIter::new(self.into_iter(), timeout)
+    }
+
+    /// Create an iterator whose delays are chosen by a closure.
+    ///
+    /// After every failed attempt but the last, the closure receives the
+    /// current [`BackoffState`] and returns a [`BackoffAction`]: sleep for a
+    /// delay of its choosing, use the scheduled delay, or give up. This
+    /// backoff still provides the max number of attempts and the scheduled
+    /// delays.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn driven_by<F>(&self, f: F) -> Driven<F>
+    where
+        F: FnMut(BackoffState) -> BackoffAction,
+    {
+        Driven::new(self.iter(), f)
+    }
+
+    /// The unjittered, unclamped delay that follows the given zero-based
+    /// attempt.
+    pub(crate) fn base_delay(&self, attempt: u32) -> Duration {
+        match &self.curve {
+            Some(curve) => curve.base_delay(attempt, self.min, self.max),
+            None => match self.growth_mode {
+                GrowthMode::Exponential => match self.fractional_factor {
+                    Some(factor) => scale_duration(self.min, powi(factor, attempt)),
+                    None => {
+                        let curve = Exponential {
+                            factor: self.factor,
+                        };
+                        curve.base_delay(attempt, self.min, self.max)
+                    }
+                },
+                GrowthMode::Linear => match self.fractional_factor {
+                    Some(factor) => scale_duration(self.min, 1.0 + factor * attempt as f64),
+                    None => {
+                        let steps = self.factor.saturating_mul(attempt).saturating_add(1);
+                        self.min.saturating_mul(steps)
+                    }
+                },
+                GrowthMode::Constant => self.min,
+            },
+        }
+    }
+
+    /// The unjittered delay that follows the given zero-based attempt, capped
+    /// by the delay cap but not yet clamped to `[min, max]`.
+    pub(crate) fn capped_delay(&self, attempt: u32) -> Duration {
+        self.apply_delay_cap(self.base_delay(attempt))
+    }
+
+    /// The unjittered delay that follows the given zero-based attempt,
+    /// capped and clamped to `[min, max]`.
+    pub(crate) fn clamped_delay(&self, attempt: u32) -> Duration {
+        self.capped_delay(attempt).clamp(self.min, self.max)
+    }
+
+    /// Apply the delay cap to an unjittered delay.
+    pub(crate) fn apply_delay_cap(&self, duration: Duration) -> Duration {
+        match self.delay_cap {
+            Some(cap) => duration.min(cap).max(self.min),
+            None => duration,
+        }
+    }
+
+    /// The lower bound for a jittered delay, given the delay before it.
+    pub(crate) fn floor(&self, previous: Option<Duration>) -> Duration {
+        match self.floor {
+            Floor::Min => self.min,
+            Floor::Previous => previous.unwrap_or(self.min),
+            Floor::None => Duration::ZERO,
+        }
+    }
+
+    /// The unjittered delays between attempts, as runs of consecutive
+    /// delays.
+    ///
+    /// Once the schedule plateaus every remaining step is identical, so the
+    /// rest of it is a single run. Delays which only grow a little from one
+    /// step to the next are grouped into runs too. This keeps walking the
+    /// schedule cheap even for `u32::MAX` attempts. Custom curves may go
+    /// anywhere, so unless they never shrink they're walked step by step.
+    pub(crate) fn steps(&self) -> impl Iterator<Item = Step> + '_ {
+        let sleeps = self.max_attempts.saturating_sub(1);
+        let monotonic = self.curve.as_ref().is_none_or(|curve| curve.is_monotonic());
+        let plateau = match monotonic {
+            true => self.plateau_attempt(sleeps),
+            false => sleeps,
+        };
+        let mut attempt = 0;
+        iter::from_fn(move || {
+            if attempt >= sleeps {
+                return None;
+            }
+            let first = self.clamped_delay(attempt);
+            let end = if attempt >= plateau {
+                sleeps
+            } else if monotonic {
+                self.run_end(attempt, first, plateau)
+            } else {
+                attempt + 1
+            };
+            let last = match end - attempt {
+                1 => first,
+                _ => self.clamped_delay(end - 1),
+            };
+            let count = end - attempt;
+            attempt = end;
+            Some(Step { first, last, count })
+        })
+    }
+
+    /// Get the first attempt from which every delay is the same, given that
+    /// the delays never shrink.
+    fn plateau_attempt(&self, sleeps: u32) -> u32 {
+        let Some(last_attempt) = sleeps.checked_sub(1) else {
+            return 0;
+        };
+        let last = self.clamped_delay(last_attempt);
+        let (mut low, mut high) = (0, last_attempt);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.clamped_delay(mid) == last {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        low
+    }
+
+    /// Get the end of the run of delays that starts at `attempt`, given that
+    /// the delays never shrink. The run stays within a fraction of a percent
+    /// of its first delay, and stops short of the plateau.
+    fn run_end(&self, attempt: u32, first: Duration, plateau: u32) -> u32 {
+        let limit = first.saturating_add(first / 128);
+        let (mut low, mut high) = (attempt + 1, plateau);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.clamped_delay(mid) > limit {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        low
+    }
+
+    /// Get the first attempt whose unjittered delay is at the max duration.
+    pub(crate) fn ceiling_attempt(&self) -> Option<u32> {
+        let mut attempt = 0;
+        for step in self.steps() {
+            if step.first >= self.max {
+                return Some(attempt);
+            }
+            attempt += step.count;
+        }
+        None
+    }
+
+    /// Sum `f` over every sleep in the schedule.
+    pub(crate) fn sum_steps(&self, f: impl Fn(Duration) -> Duration) -> Duration {
+        self.steps().fold(Duration::ZERO, |total, step| {
+            total.saturating_add(step.sum(&f))
+        })
+    }
+
+    /// Approximate the given percentile of the total time spent sleeping.
+    ///
+    /// Each jittered delay is modelled as uniformly spread across its jitter
+    /// band, and their sum as normally distributed.
+    #[cfg(feature = "std")]
+    fn percentile_total_delay(&self, z_score: f64) -> Duration {
+        let jitter = self.jitter as f64;
+        let (mut mean, mut variance) = (0f64, 0f64);
+        for step in self.steps() {
+            let (first, last) = (step.first.as_secs_f64(), step.last.as_secs_f64());
+            let count = step.count as f64;
+            mean += (first + last) / 2.0 * count;
+            // The mean square of delays growing evenly from first to last.
+            let square = (first * first + first * last + last * last) / 3.0;
+            variance += square * jitter.powi(2) / 3.0 * count;
+        }
+        let total = mean + z_score * variance.sqrt();
+        Duration::try_from_secs_f64(total).unwrap_or(Duration::MAX)
+    }
+
+    /// The lowest and highest delays jitter can turn an unjittered delay
+    /// into.
+    pub(crate) fn jitter_bounds(&self, duration: Duration) -> (Duration, Duration) {
+        let floor = self.floor(None);
+        let (low, high) = match self.jitter_strategy {
+            JitterStrategy::Proportional => return self.proportional_jitter_bounds(duration),
+            JitterStrategy::Full => (Duration::ZERO, duration),
+            JitterStrategy::Equal => (duration / 2, duration),
+            JitterStrategy::Decorrelated => (self.min, self.max),
+            JitterStrategy::FullDownward => (self.min, duration),
+            JitterStrategy::Absolute(spread) => (
+                duration.saturating_sub(spread),
+                duration.saturating_add(spread),
+            ),
+        };
+        (low.clamp(floor, self.max), high.clamp(floor, self.max))
+    }
+
+    fn proportional_jitter_bounds(&self, duration: Duration) -> (Duration, Duration) {
+        let jitter = into_iter::max_jitter(self, duration);
+        let low = duration.saturating_sub(jitter);
+        let high = duration.saturating_add(jitter);
+        if self.is_banded() {
+            return (low, high);
+        }
+        let floor = self.floor(None);
+        (low.clamp(floor, self.max), high.clamp(floor, self.max))
+    }
+
+    /// Whether jitter is kept as a band around an equal min and max, rather
+    /// than being clamped away.
+    pub(crate) fn is_banded(&self) -> bool {
+        self.jitter_strategy == JitterStrategy::Proportional
+            && self.min == self.max
+            && !self.strict_bounds
+    }
+}
+
+/// A run of consecutive sleeps, with unjittered delays growing from `first`
+/// to `last`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Step {
+    pub(crate) first: Duration,
+    pub(crate) last: Duration,
+    pub(crate) count: u32,
+}
+
+impl Step {
+    /// Sum `f` over every sleep in the run, treating the delays as growing
+    /// evenly from the first to the last. This is exact for a single step, a
+    /// plateau, or linear growth.
+    fn sum(&self, f: impl Fn(Duration) -> Duration) -> Duration {
+        if self.count == 1 {
+            return f(self.first);
+        }
+        let ends = f(self.first).as_nanos() + f(self.last).as_nanos();
+        let nanos = ends * self.count as u128 / 2;
+        match u64::try_from(nanos / 1_000_000_000) {
+            Ok(secs) => Duration::new(secs, (nanos % 1_000_000_000) as u32),
+            Err(_) => Duration::MAX,
+        }
+    }
+}
+
+/// Raise `base` to an integer power, without needing `std` for `f64::powi`.
+fn powi(mut base: f64, mut exp: u32) -> f64 {
+    let mut result = 1.0;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result *= base;
+        }
+        base *= base;
+        exp >>= 1;
+    }
+    result
+}
+
+/// Multiply a duration by a float, saturating at `Duration::MAX`.
+fn scale_duration(duration: Duration, scale: f64) -> Duration {
+    // Only a delay too long to represent saturates. Scaling a zero delay by
+    // an infinite factor is NaN, which stays zero.
+    let secs = duration.as_secs_f64() * scale;
+    match Duration::try_from_secs_f64(secs) {
+        Ok(duration) => duration,
+        Err(_) if secs > 0.0 => Duration::MAX,
+        Err(_) => Duration::ZERO,
+    }
+}
+
+impl Default for Backoff {
+    /// Make 3 attempts, waiting between 100ms and 10s.
+    fn default() -> Self {
+        Self::new(3, Duration::from_millis(100), Duration::from_secs(10))
+    }
+}
+
+/// Custom curves and retry callbacks are only equal if they're the same
+/// instance. The [name](Backoff::with_name) is ignored.
+impl PartialEq for Backoff {
+    fn eq(&self, other: &Self) -> bool {
+        let curve = match (&self.curve, &other.curve) {
+            (Some(curve), Some(other)) => Arc::ptr_eq(curve, other),
+            (curve, other) => curve.is_none() && other.is_none(),
+        };
+        let on_retry = match (&self.on_retry, &other.on_retry) {
+            (Some(on_retry), Some(other)) => Arc::ptr_eq(&on_retry.0, &other.0),
+            (on_retry, other) => on_retry.is_none() && other.is_none(),
+        };
+        curve
+            && on_retry
+            && self.max_attempts == other.max_attempts
+            && self.min == other.min
+            && self.max == other.max
+            && self.jitter == other.jitter
+            && self.jitter_bias == other.jitter_bias
+            && self.factor == other.factor
+            && self.fractional_factor == other.fractional_factor
+            && self.growth_mode == other.growth_mode
+            && self.delay_cap == other.delay_cap
+            && self.jitter_cap == other.jitter_cap
+            && self.floor == other.floor
+            && self.strict_bounds == other.strict_bounds
+            && self.lifetime_max_attempts == other.lifetime_max_attempts
+            && self.initial_delay == other.initial_delay
+            && self.first_retry_delay == other.first_retry_delay
+            && self.smoothing == other.smoothing
+            && self.jitter_strategy == other.jitter_strategy
+            && self.total_delay_budget == other.total_delay_budget
     }
 }
 
-impl<'b> IntoIterator for &'b Backoff {
+#[cfg(feature = "std")]
+impl IntoIterator for &Backoff {
     type Item = Option<Duration>;
     type IntoIter = IntoIter;
 
     fn into_iter(self) -> Self::IntoIter {
-        Self::IntoIter::new(self.clone())
+        self.clone().into_iter()
     }
 }
 
+#[cfg(feature = "std")]
 impl IntoIterator for Backoff {
     type Item = Option<Duration>;
     type IntoIter = IntoIter;
 
     fn into_iter(self) -> Self::IntoIter {
-        Self::IntoIter::new(self)
+        Self::IntoIter::new(self, Rng::new())
     }
 }
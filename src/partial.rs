@@ -0,0 +1,91 @@
+use crate::Floor;
+use core::time::Duration;
+
+/// A partial backoff configuration, used to override some settings of a
+/// base configuration.
+///
+/// Settings which are left unset fall through to the base. See
+/// [`Backoff::merge`](crate::Backoff::merge).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PartialBackoff {
+    pub(crate) max_attempts: Option<u32>,
+    pub(crate) min: Option<Duration>,
+    pub(crate) max: Option<Duration>,
+    pub(crate) jitter: Option<f32>,
+    pub(crate) jitter_bias: Option<f32>,
+    pub(crate) factor: Option<u32>,
+    pub(crate) floor: Option<Floor>,
+}
+
+impl PartialBackoff {
+    /// Create a new instance which overrides nothing.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Override the max number of attempts.
+    #[inline]
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Override the min duration.
+    #[inline]
+    pub fn min(mut self, min: Duration) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// Override the max duration.
+    #[inline]
+    pub fn max(mut self, max: Duration) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Override the amount of jitter per backoff.
+    ///
+    /// ## Panics
+    /// This method panics if a number smaller than `0` or larger than `1` is
+    /// provided.
+    #[inline]
+    pub fn jitter(mut self, jitter: f32) -> Self {
+        assert!(
+            (0f32..=1f32).contains(&jitter),
+            "<exponential-backoff>: jitter must be between 0 and 1."
+        );
+        self.jitter = Some(jitter);
+        self
+    }
+
+    /// Override which direction the jitter leans towards.
+    ///
+    /// ## Panics
+    /// This method panics if a number smaller than `-1` or larger than `1` is
+    /// provided.
+    #[inline]
+    pub fn jitter_bias(mut self, bias: f32) -> Self {
+        assert!(
+            (-1f32..=1f32).contains(&bias),
+            "<exponential-backoff>: jitter bias must be between -1 and 1."
+        );
+        self.jitter_bias = Some(bias);
+        self
+    }
+
+    /// Override the growth factor.
+    #[inline]
+    pub fn factor(mut self, factor: u32) -> Self {
+        self.factor = Some(factor);
+        self
+    }
+
+    /// Override the lower bound applied to each jittered delay.
+    #[inline]
+    pub fn floor(mut self, floor: Floor) -> Self {
+        self.floor = Some(floor);
+        self
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{retry_with, Backoff, RetryStats};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Instant,
+};
+
+type Predicate<E> = Box<dyn Fn(&E) -> bool>;
+
+/// Options for [`retry_ex`].
+pub struct RetryOptions<E> {
+    retry_if: Option<Predicate<E>>,
+    deadline: Option<Instant>,
+    healthy: Option<Arc<AtomicBool>>,
+}
+
+impl<E> RetryOptions<E> {
+    /// Create a new instance which retries every error and has no deadline.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            retry_if: None,
+            deadline: None,
+            healthy: None,
+        }
+    }
+
+    /// Only retry errors for which the predicate returns `true`.
+    #[inline]
+    pub fn retry_if(mut self, predicate: impl Fn(&E) -> bool + 'static) -> Self {
+        self.retry_if = Some(Box::new(predicate));
+        self
+    }
+
+    /// Stop retrying if sleeping before the next attempt would pass the
+    /// deadline.
+    #[inline]
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    fn is_healthy(&self) -> bool {
+        match &self.healthy {
+            Some(healthy) => healthy.load(Ordering::Acquire),
+            None => true,
+        }
+    }
+}
+
+impl<E> Default for RetryOptions<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> fmt::Debug for RetryOptions<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryOptions")
+            .field("retry_if", &self.retry_if.is_some())
+            .field("deadline", &self.deadline)
+            .field("healthy", &self.healthy)
+            .finish()
+    }
+}
+
+/// The outcome of [`retry_ex`].
+#[derive(Debug)]
+pub enum RetryOutcome<T, E> {
+    /// The operation succeeded.
+    Succeeded {
+        /// The value the operation returned.
+        value: T,
+        /// The number of attempts made, including the successful one.
+        attempts: u32,
+    },
+    /// The operation failed on every attempt the backoff allows.
+    Exhausted {
+        /// The error returned by the last attempt.
+        last_error: E,
+        /// The number of attempts made.
+        attempts: u32,
+    },
+    /// Retrying was stopped before the attempts ran out.
+    Aborted {
+        /// Why retrying was stopped.
+        reason: AbortReason<E>,
+    },
+}
+
+/// Why [`retry_ex`] stopped retrying early.
+#[derive(Debug)]
+pub enum AbortReason<E> {
+    /// The retry predicate rejected this error.
+    NotRetryable(E),
+    /// Sleeping before the next attempt would have passed the deadline. Holds
+    /// the error of the last attempt.
+    DeadlineExceeded(E),
+    /// The health flag was cleared, so the dependency is known to be down.
+    /// Holds the error of the last attempt.
+    Unhealthy(E),
+}
+
+/// Run `op` until it succeeds, sleeping between attempts according to the
+/// backoff, and return the last error once the attempts run out.
+///
+/// The operation always runs at least once, even if the backoff allows no
+/// attempts. If the backoff has an [initial
+/// delay](Backoff::set_initial_delay), it's slept once before the first
+/// attempt. See [`retry_ex`] for more control over when to stop retrying.
+///
+/// # Examples
+///
+/// ```rust
+/// use exponential_backoff::{retry, Backoff};
+/// use std::{fs, time::Duration};
+///
+/// let backoff = Backoff::new(3, Duration::from_millis(10), None);
+/// let readme = retry(&backoff, || fs::read_to_string("README.md"));
+/// assert!(readme.is_ok());
+/// ```
+pub fn retry<T, E>(backoff: &Backoff, op: impl FnMut() -> Result<T, E>) -> Result<T, E> {
+    retry_with(backoff, thread::sleep, op)
+}
+
+/// Run `f` until it succeeds, sleeping between attempts according to the
+/// backoff.
+///
+/// The operation always runs at least once, even if the backoff allows no
+/// attempts. If the backoff has an [initial
+/// delay](Backoff::set_initial_delay), it's slept once before the first
+/// attempt.
+///
+/// # Examples
+///
+/// ```rust
+/// use exponential_backoff::{retry_ex, Backoff, RetryOptions, RetryOutcome};
+/// use std::{fs, time::Duration};
+///
+/// let backoff = Backoff::new(3, Duration::from_millis(10), None);
+/// let options = RetryOptions::new().retry_if(|err: &std::io::Error| {
+///     err.kind() != std::io::ErrorKind::PermissionDenied
+/// });
+///
+/// match retry_ex(&backoff, options, || fs::read_to_string("README.md")) {
+///     RetryOutcome::Succeeded { value, .. } => println!("{}", value),
+///     RetryOutcome::Exhausted { last_error, .. } => eprintln!("{}", last_error),
+///     RetryOutcome::Aborted { reason } => eprintln!("{:?}", reason),
+/// }
+/// ```
+pub fn retry_ex<T, E>(
+    backoff: &Backoff,
+    options: RetryOptions<E>,
+    f: impl FnMut() -> Result<T, E>,
+) -> RetryOutcome<T, E> {
+    retry_with_stats(backoff, options, f).0
+}
+
+/// Run `f` until it succeeds, like [`retry_ex`], and also report statistics
+/// about the run.
+///
+/// See [`RetryStats`] for what is recorded.
+pub fn retry_with_stats<T, E>(
+    backoff: &Backoff,
+    options: RetryOptions<E>,
+    f: impl FnMut() -> Result<T, E>,
+) -> (RetryOutcome<T, E>, RetryStats) {
+    let mut stats = RetryStats::default();
+    let outcome = run(backoff, options, f, &mut stats);
+    (outcome, stats)
+}
+
+fn run<T, E>(
+    backoff: &Backoff,
+    options: RetryOptions<E>,
+    mut f: impl FnMut() -> Result<T, E>,
+    stats: &mut RetryStats,
+) -> RetryOutcome<T, E> {
+    if let Some(delay) = backoff.initial_delay {
+        thread::sleep(delay);
+    }
+    let mut durations = backoff.iter().within_budget();
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        stats.attempts = attempts;
+        let err = match f() {
+            Ok(value) => return RetryOutcome::Succeeded { value, attempts },
+            Err(err) => err,
+        };
+
+        if let Some(retry_if) = &options.retry_if {
+            if !retry_if(&err) {
+                let reason = AbortReason::NotRetryable(err);
+                return RetryOutcome::Aborted { reason };
+            }
+        }
+
+        let duration = match durations.next().flatten() {
+            Some(duration) => duration,
+            None => {
+                return RetryOutcome::Exhausted {
+                    last_error: err,
+                    attempts,
+                }
+            }
+        };
+
+        if let Some(deadline) = options.deadline {
+            // A delay too long to represent is past any deadline.
+            let wake = Instant::now().checked_add(duration);
+            if wake.is_none_or(|wake| wake > deadline) {
+                let reason = AbortReason::DeadlineExceeded(err);
+                return RetryOutcome::Aborted { reason };
+            }
+        }
+        if !options.is_healthy() {
+            let reason = AbortReason::Unhealthy(err);
+            return RetryOutcome::Aborted { reason };
+        }
+        thread::sleep(duration);
+        let scheduled = durations.unjittered().unwrap_or(duration);
+        stats.scheduled_total = stats.scheduled_total.saturating_add(scheduled);
+        stats.realized_total = stats.realized_total.saturating_add(duration);
+
+        // The flag may have been cleared while we were sleeping.
+        if !options.is_healthy() {
+            let reason = AbortReason::Unhealthy(err);
+            return RetryOutcome::Aborted { reason };
+        }
+    }
+}
+
+/// Run `f` until it succeeds, like [`retry_ex`], but stop retrying as soon as
+/// `healthy` is set to `false`.
+///
+/// This integrates with an external health check or circuit breaker: once
+/// the dependency is known to be down, retrying it is pointless. The flag is
+/// checked both before and after sleeping, and the operation always runs at
+/// least once.
+pub fn retry_while_healthy<T, E>(
+    backoff: &Backoff,
+    healthy: Arc<AtomicBool>,
+    f: impl FnMut() -> Result<T, E>,
+) -> RetryOutcome<T, E> {
+    let mut options = RetryOptions::new();
+    options.healthy = Some(healthy);
+    retry_ex(backoff, options, f)
+}
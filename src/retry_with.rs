@@ -0,0 +1,50 @@
+#[cfg(feature = "std")]
+use crate::Backoff;
+use crate::{IntoIter, JitterRng};
+use core::time::Duration;
+
+/// Run `op` until it succeeds, like [`retry`](crate::retry), but sleep using
+/// `sleep`.
+///
+/// This makes the retry loop reusable where `std::thread::sleep` isn't
+/// wanted, such as in tests or on platforms with their own timers.
+#[cfg(feature = "std")]
+pub fn retry_with<T, E>(
+    backoff: &Backoff,
+    sleep: impl FnMut(Duration),
+    op: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    retry_with_iter(backoff.iter(), sleep, op)
+}
+
+/// Run `op` until it succeeds, like [`retry_with`], but take the delays from
+/// `durations`.
+///
+/// This works without the `std` feature, where there's no entropy to seed
+/// [`Backoff::iter`] from. Create the iterator with
+/// [`Backoff::iter_seeded`] or [`Backoff::iter_with_rng`] instead.
+///
+/// [`retry_with`]: crate::retry_with
+/// [`Backoff::iter`]: crate::Backoff::iter
+/// [`Backoff::iter_seeded`]: crate::Backoff::iter_seeded
+/// [`Backoff::iter_with_rng`]: crate::Backoff::iter_with_rng
+pub fn retry_with_iter<T, E, R: JitterRng>(
+    durations: IntoIter<R>,
+    mut sleep: impl FnMut(Duration),
+    mut op: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    if let Some(delay) = durations.backoff().initial_delay {
+        sleep(delay);
+    }
+    let mut durations = durations.within_budget();
+    loop {
+        let err = match op() {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        match durations.next().flatten() {
+            Some(duration) => sleep(duration),
+            None => return Err(err),
+        }
+    }
+}
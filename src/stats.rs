@@ -0,0 +1,81 @@
+use core::time::Duration;
+
+/// Summary statistics comparing jittered schedules to the unjittered one.
+///
+/// Created by [`Backoff::compare_jitter`](crate::Backoff::compare_jitter).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JitterComparison {
+    /// The total time spent sleeping without jitter.
+    pub deterministic: Duration,
+    /// The mean total time spent sleeping across the jittered samples.
+    pub mean: Duration,
+    /// The standard deviation of the jittered totals.
+    pub stddev: Duration,
+    /// The shortest jittered total.
+    pub min: Duration,
+    /// The longest jittered total.
+    pub max: Duration,
+}
+
+impl JitterComparison {
+    #[cfg(feature = "std")]
+    pub(crate) fn new(deterministic: Duration, samples: impl Iterator<Item = Duration>) -> Self {
+        // Welford's online algorithm, so we don't need to store the samples.
+        let (mut count, mut mean, mut m2) = (0f64, 0f64, 0f64);
+        let (mut min, mut max) = (Duration::MAX, Duration::ZERO);
+        for sample in samples {
+            min = min.min(sample);
+            max = max.max(sample);
+            let value = sample.as_secs_f64();
+            count += 1.0;
+            let delta = value - mean;
+            mean += delta / count;
+            m2 += delta * (value - mean);
+        }
+
+        if count == 0.0 {
+            min = Duration::ZERO;
+        }
+        let variance = if count > 0.0 { m2 / count } else { 0.0 };
+        let to_duration = |secs: f64| Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX);
+        Self {
+            deterministic,
+            mean: to_duration(mean),
+            stddev: to_duration(variance.sqrt()),
+            min,
+            max,
+        }
+    }
+}
+
+/// A limit broken by a backoff schedule.
+///
+/// Returned by [`Backoff::assert_within`](crate::Backoff::assert_within).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    /// The delay after this attempt may exceed the per-step limit.
+    Step {
+        /// The zero-based attempt.
+        attempt: u32,
+        /// The longest the delay can be.
+        upper_bound: Duration,
+    },
+    /// The total time spent sleeping may exceed the limit.
+    Total {
+        /// The longest the total can be.
+        upper_bound: Duration,
+    },
+}
+
+/// Statistics about a completed retry run.
+///
+/// Created by [`retry_with_stats`](crate::retry_with_stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetryStats {
+    /// The number of attempts made.
+    pub attempts: u32,
+    /// The total time the schedule called for sleeping, without jitter.
+    pub scheduled_total: Duration,
+    /// The total time actually slept, with jitter.
+    pub realized_total: Duration,
+}
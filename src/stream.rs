@@ -0,0 +1,89 @@
+use crate::IntoIter;
+use futures_core::Stream;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+type Sleeper = Box<dyn FnMut(Duration) -> Sleep + Send>;
+
+/// An exponential backoff stream which sleeps between attempts.
+///
+/// The stream yields once per attempt. The first attempt is yielded straight
+/// away, and every attempt after it once the delay that follows the previous
+/// attempt has been slept. Once the final attempt has been yielded the stream
+/// ends, without sleeping after it. This keeps the retry loop to
+/// `while stream.next().await.is_some() { ... }`.
+///
+/// Created by [`Backoff::stream_with`](crate::Backoff::stream_with), or
+/// [`Backoff::stream`](crate::Backoff::stream) with the `tokio` feature.
+pub struct BackoffStream {
+    inner: IntoIter,
+    sleeper: Sleeper,
+    sleeping: Option<Sleep>,
+    pending: Option<Duration>,
+}
+
+impl BackoffStream {
+    pub(crate) fn new<F, Fut>(inner: IntoIter, mut sleeper: F) -> Self
+    where
+        F: FnMut(Duration) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Self {
+            inner,
+            sleeper: Box::new(move |duration| Box::pin(sleeper(duration))),
+            sleeping: None,
+            pending: None,
+        }
+    }
+}
+
+impl fmt::Debug for BackoffStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BackoffStream")
+            .field("inner", &self.inner)
+            .field("sleeping", &self.sleeping.is_some())
+            .field("pending", &self.pending)
+            .finish()
+    }
+}
+
+impl Stream for BackoffStream {
+    type Item = ();
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(sleeping) = &mut this.sleeping {
+                if sleeping.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                this.sleeping = None;
+            }
+
+            // Sleep through the delay which follows the previous attempt.
+            if let Some(duration) = this.pending.take() {
+                this.sleeping = Some((this.sleeper)(duration));
+                continue;
+            }
+
+            return match this.inner.next() {
+                Some(duration) => {
+                    this.pending = duration;
+                    Poll::Ready(Some(()))
+                }
+                None => Poll::Ready(None),
+            };
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
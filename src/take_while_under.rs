@@ -0,0 +1,48 @@
+use crate::IntoIter;
+use core::{iter, time::Duration};
+
+/// An exponential backoff iterator which stops once a delay would exceed a
+/// limit.
+///
+/// Created by [`IntoIter::take_while_under`].
+#[derive(Debug, Clone)]
+pub struct TakeWhileUnder {
+    inner: IntoIter,
+    limit: Duration,
+    done: bool,
+}
+
+impl TakeWhileUnder {
+    pub(crate) fn new(inner: IntoIter, limit: Duration) -> Self {
+        Self {
+            inner,
+            limit,
+            done: false,
+        }
+    }
+}
+
+impl iter::Iterator for TakeWhileUnder {
+    type Item = Option<Duration>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        // The retry callback only runs for delays we yield.
+        match self.inner.next_quiet()? {
+            (_, Some(duration)) if duration > self.limit => {
+                self.done = true;
+                Some(None)
+            }
+            (attempt, duration) => {
+                if let Some(duration) = duration {
+                    self.inner.on_retry(attempt, duration);
+                }
+                Some(duration)
+            }
+        }
+    }
+}
@@ -1,7 +1,20 @@
 extern crate exponential_backoff;
 
-use exponential_backoff::Backoff;
-use std::{fs, thread, time::Duration};
+use exponential_backoff::{
+    retry, retry_ex, retry_while_healthy, retry_with, retry_with_iter, retry_with_stats,
+    AbortReason, Advisory, Backoff, BackoffAction, BackoffError, BackoffState, BudgetedIter,
+    CircuitAwareIter, CompiledBackoff, Control, Curve, Fibonacci, Floor, GrowthMode, JitterRng,
+    JitterStrategy, Linear, PartialBackoff, RetryOptions, RetryOutcome, TimeoutMode, Violation,
+};
+use std::{
+    cmp, fs,
+    sync::{
+        atomic::{AtomicBool, AtomicU8, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
 #[test]
 fn doesnt_crash() -> std::io::Result<()> {
@@ -44,6 +57,24 @@ fn iter_completes() {
     assert_eq!(counter, attempts);
 }
 
+#[test]
+fn it_has_the_right_min_value() {
+    let mut backoff = Backoff::new(4, Duration::from_secs(1), None);
+    backoff.set_jitter(0.0);
+    let durations: Vec<_> = backoff.iter().collect();
+    let expected = [Some(1), Some(2), Some(4), None].map(|d| d.map(Duration::from_secs));
+    assert_eq!(durations, expected);
+
+    // The max still clamps, and the schedule still ends the same way.
+    backoff.set_max(Duration::from_millis(1500));
+    let mut iter = backoff.iter();
+    assert_eq!(iter.next(), Some(Some(Duration::from_secs(1))));
+    assert_eq!(iter.next(), Some(Some(Duration::from_millis(1500))));
+    assert_eq!(iter.next(), Some(Some(Duration::from_millis(1500))));
+    assert_eq!(iter.next(), Some(None));
+    assert_eq!(iter.next(), None);
+}
+
 #[test]
 fn into_iter_completes() {
     let attempts = 3;
@@ -86,3 +117,1966 @@ fn no_attempts() {
     }
     assert_eq!(count, 0);
 }
+
+#[test]
+fn analytic_methods_handle_max_attempts() {
+    let backoff = Backoff::new(u32::MAX, Duration::MAX, None);
+    assert_eq!(backoff.duration_at(0), Some(Duration::MAX));
+    assert_eq!(backoff.duration_at(u32::MAX - 1), None);
+    assert_eq!(backoff.expected_total_wait(), Duration::MAX);
+    let len = u32::MAX as usize;
+    assert_eq!(backoff.iter().size_hint(), (len, Some(len)));
+
+    let mut backoff = Backoff::new(u32::MAX, Duration::from_millis(1), None);
+    backoff.set_factor(1);
+    let expected = Duration::from_millis(u32::MAX as u64 - 1);
+    assert_eq!(backoff.expected_total_wait(), expected);
+
+    let max = Duration::from_secs(1);
+    let backoff = Backoff::new(u32::MAX, Duration::from_millis(100), max);
+    let ramp = Duration::from_millis(100 + 200 + 400 + 800);
+    let expected = ramp + max * (u32::MAX - 5);
+    assert_eq!(backoff.expected_total_wait(), expected);
+
+    // Linear growth never plateaus without a max.
+    let mut backoff = Backoff::new(u32::MAX, Duration::from_millis(1), None);
+    backoff.set_growth_mode(GrowthMode::Linear);
+    backoff.set_factor(1);
+    let sleeps = u32::MAX as u64 - 1;
+    let expected = Duration::from_millis(sleeps * (sleeps + 1) / 2);
+    assert_eq!(backoff.expected_total_wait(), expected);
+    assert!(backoff.max_total_delay() > expected);
+    assert!(backoff.min_total_delay() < expected);
+    assert_eq!(backoff.time_to_ceiling(), Duration::MAX);
+    assert!(backoff.assert_within(Duration::MAX, Duration::MAX).is_ok());
+    let sized = backoff.sized_for_p99(Duration::from_secs(1));
+    assert!(sized.expected_total_wait() < Duration::from_secs(1));
+
+    // Neither do the built-in curves.
+    backoff.set_curve(Linear);
+    assert_eq!(backoff.expected_total_wait(), expected);
+    backoff.set_curve(Fibonacci);
+    assert!(backoff.expected_total_wait() > expected);
+    assert_eq!(backoff.time_to_ceiling(), Duration::MAX);
+}
+
+#[test]
+fn jitter_bias_shifts_delays() {
+    let min = Duration::from_millis(100);
+    let mut backoff = Backoff::new(16, min, None);
+
+    backoff.set_jitter_bias(1.0);
+    for (attempt, duration) in backoff.iter().enumerate() {
+        if let (Some(duration), Some(base)) = (duration, backoff.duration_at(attempt as u32)) {
+            assert!(duration >= base);
+        }
+    }
+
+    backoff.set_jitter_bias(-1.0);
+    for (attempt, duration) in backoff.iter().enumerate() {
+        if let (Some(duration), Some(base)) = (duration, backoff.duration_at(attempt as u32)) {
+            assert!(duration <= base);
+        }
+    }
+}
+
+#[test]
+#[should_panic]
+fn jitter_bias_out_of_range() {
+    let mut backoff = Backoff::new(3, Duration::from_millis(10), None);
+    backoff.set_jitter_bias(1.5);
+}
+
+#[derive(Debug)]
+struct Stepwise;
+
+impl Curve for Stepwise {
+    fn base_delay(&self, attempt: u32, min: Duration, _max: Duration) -> Duration {
+        match attempt {
+            0 | 1 => min,
+            2 | 3 => min * 5,
+            _ => min * 10,
+        }
+    }
+}
+
+#[test]
+fn custom_curve() {
+    let min = Duration::from_millis(10);
+    let mut backoff = Backoff::new(7, min, Duration::from_millis(80));
+    backoff.set_curve(Stepwise);
+
+    let schedule: Vec<_> = (0..7).map(|n| backoff.duration_at(n)).collect();
+    let expected = [10, 10, 50, 50, 80, 80].map(|ms| Some(Duration::from_millis(ms)));
+    assert_eq!(&schedule[..6], &expected);
+    assert_eq!(schedule[6], None);
+    assert_eq!(backoff.expected_total_wait(), Duration::from_millis(280));
+
+    for (attempt, duration) in backoff.iter().enumerate() {
+        if let Some(duration) = duration {
+            let base = backoff.duration_at(attempt as u32).unwrap();
+            assert!(duration >= base.mul_f32(0.7) && duration <= base.mul_f32(1.3));
+        }
+    }
+}
+
+#[test]
+fn built_in_curves() {
+    let min = Duration::from_millis(10);
+    let mut backoff = Backoff::new(6, min, None);
+
+    backoff.set_curve(Linear);
+    let schedule: Vec<_> = (0..5).filter_map(|n| backoff.duration_at(n)).collect();
+    assert_eq!(schedule, [10, 20, 30, 40, 50].map(Duration::from_millis));
+
+    backoff.set_curve(Fibonacci);
+    let schedule: Vec<_> = (0..5).filter_map(|n| backoff.duration_at(n)).collect();
+    assert_eq!(schedule, [10, 20, 30, 50, 80].map(Duration::from_millis));
+
+    // An unbounded Fibonacci schedule keeps growing well past attempt 50, and
+    // only saturates at `Duration::MAX`.
+    let mut backoff = Backoff::new(256, min, None);
+    backoff.set_curve(Fibonacci);
+    backoff.set_jitter(0.0);
+    let schedule: Vec<_> = (40..80).filter_map(|n| backoff.duration_at(n)).collect();
+    assert_eq!(schedule.len(), 40);
+    assert!(schedule.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(backoff.duration_at(254), Some(Duration::MAX));
+}
+
+#[test]
+fn delay_cap_applies_before_jitter() {
+    let min = Duration::from_millis(10);
+    let cap = Duration::from_millis(100);
+    let max = Duration::from_millis(200);
+    let backoff = Backoff::new(32, min, max).with_delay_cap(cap);
+
+    assert_eq!(backoff.duration_at(10), Some(cap));
+    assert_eq!(
+        backoff.expected_total_wait(),
+        Duration::from_millis(10 + 20 + 40 + 80) + cap * 27
+    );
+
+    let mut above = false;
+    let mut below = false;
+    for duration in backoff.iter().skip(4).flatten() {
+        assert!(duration >= cap.mul_f32(0.7) && duration <= cap.mul_f32(1.3));
+        above |= duration > cap;
+        below |= duration < cap;
+    }
+    assert!(above && below);
+}
+
+#[test]
+fn retry_ex_outcomes() {
+    let backoff = Backoff::new(3, Duration::from_millis(1), Duration::from_millis(5));
+
+    let mut calls = 0;
+    let outcome = retry_ex(&backoff, RetryOptions::new(), || {
+        calls += 1;
+        if calls < 2 {
+            Err("flaky")
+        } else {
+            Ok(calls)
+        }
+    });
+    assert!(matches!(
+        outcome,
+        RetryOutcome::Succeeded {
+            value: 2,
+            attempts: 2
+        }
+    ));
+
+    let outcome = retry_ex(&backoff, RetryOptions::new(), || Err::<(), _>("down"));
+    assert!(matches!(
+        outcome,
+        RetryOutcome::Exhausted {
+            last_error: "down",
+            attempts: 3
+        }
+    ));
+
+    let options = RetryOptions::new().retry_if(|err: &&str| *err != "fatal");
+    let outcome = retry_ex(&backoff, options, || Err::<(), _>("fatal"));
+    assert!(matches!(
+        outcome,
+        RetryOutcome::Aborted {
+            reason: AbortReason::NotRetryable("fatal")
+        }
+    ));
+
+    let options = RetryOptions::new().deadline(Instant::now());
+    let outcome = retry_ex(&backoff, options, || Err::<(), _>("slow"));
+    assert!(matches!(
+        outcome,
+        RetryOutcome::Aborted {
+            reason: AbortReason::DeadlineExceeded("slow")
+        }
+    ));
+
+    // A delay that overflows `Instant` is past the deadline.
+    let backoff = Backoff::new(3, Duration::MAX, None);
+    let deadline = Instant::now() + Duration::from_secs(60);
+    let options = RetryOptions::new().deadline(deadline);
+    let outcome = retry_ex(&backoff, options, || Err::<(), _>("slow"));
+    assert!(matches!(
+        outcome,
+        RetryOutcome::Aborted {
+            reason: AbortReason::DeadlineExceeded("slow")
+        }
+    ));
+}
+
+#[test]
+fn capped_attempts_end_with_sentinel() {
+    let backoff = Backoff::new(8, Duration::from_millis(10), None);
+
+    let durations: Vec<_> = backoff.clone().into_iter_capped_attempts(3).collect();
+    assert_eq!(durations.len(), 3);
+    assert!(durations[..2].iter().all(Option::is_some));
+    assert_eq!(durations[2], None);
+
+    let durations: Vec<_> = backoff.clone().into_iter_capped_attempts(20).collect();
+    assert_eq!(durations.len(), 8);
+    assert_eq!(durations[7], None);
+
+    assert_eq!(backoff.into_iter_capped_attempts(0).count(), 0);
+}
+
+#[test]
+fn ceiling_can_be_raised_and_lowered() {
+    let backoff = Backoff::new(6, Duration::from_millis(100), Duration::from_millis(400));
+
+    let lowered = backoff.with_ceiling(Duration::from_millis(200));
+    let schedule: Vec<_> = (0..5).filter_map(|n| lowered.duration_at(n)).collect();
+    assert_eq!(
+        schedule,
+        [100, 200, 200, 200, 200].map(Duration::from_millis)
+    );
+
+    let raised = backoff.with_ceiling(Duration::from_secs(10));
+    let schedule: Vec<_> = (0..5).filter_map(|n| raised.duration_at(n)).collect();
+    assert_eq!(
+        schedule,
+        [100, 200, 400, 800, 1600].map(Duration::from_millis)
+    );
+
+    for duration in raised.iter().flatten() {
+        assert!(duration <= Duration::from_secs(10));
+    }
+}
+
+#[test]
+#[should_panic]
+fn ceiling_below_min() {
+    let backoff = Backoff::new(6, Duration::from_millis(100), None);
+    backoff.with_ceiling(Duration::from_millis(10));
+}
+
+#[test]
+fn jitter_can_be_capped() {
+    let base = Duration::from_secs(10);
+    let mut backoff = Backoff::new(64, base, None);
+    backoff.set_factor(1);
+    backoff.set_jitter(0.5);
+
+    let cap = Duration::from_secs(1);
+    let mut jittered = false;
+    for duration in backoff.into_iter_with_random_capped_jitter(cap).flatten() {
+        assert!(duration <= base + cap);
+        jittered |= duration != base;
+    }
+    assert!(jittered);
+
+    // The longest delay doesn't overflow while capping its jitter.
+    let mut backoff = Backoff::new(3, Duration::MAX, None);
+    backoff.set_jitter(1.0);
+    let mut iter = backoff.into_iter_with_random_capped_jitter(cap);
+    let duration = iter.next().unwrap().unwrap();
+    assert!(duration >= Duration::MAX - cap);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn schedule_as_json() {
+    let backoff = Backoff::new(4, Duration::from_millis(100), Duration::from_millis(300));
+    let schedule = backoff.schedule_json();
+    assert_eq!(
+        schedule,
+        [
+            serde_json::json!({ "attempt": 0, "delay_ms": 100, "capped": false }),
+            serde_json::json!({ "attempt": 1, "delay_ms": 200, "capped": false }),
+            serde_json::json!({ "attempt": 2, "delay_ms": 300, "capped": true }),
+        ]
+    );
+}
+
+#[test]
+fn gcp_cloud_tasks_defaults() {
+    let backoff = Backoff::new_gcp_cloud_tasks_default();
+    assert_eq!(backoff.iter().count(), 18);
+    assert_eq!(backoff.duration_at(16), Some(Duration::from_secs(3600)));
+
+    let min = Duration::from_millis(100);
+    let backoff = Backoff::new_gcp_cloud_tasks_custom(min, Duration::from_secs(86400), 16);
+    assert_eq!(backoff.duration_at(0), Some(min));
+    assert_eq!(backoff.duration_at(16), Some(min * 2u32.pow(16)));
+    assert_eq!(backoff.duration_at(17), None);
+}
+
+#[test]
+fn sample_step_is_reproducible() {
+    let backoff = Backoff::new(8, Duration::from_millis(100), Duration::from_secs(1));
+    for attempt in 0..7 {
+        let first = backoff.sample_step(attempt, &mut fastrand::Rng::with_seed(7));
+        let second = backoff.sample_step(attempt, &mut fastrand::Rng::with_seed(7));
+        assert_eq!(first, second);
+
+        let base = backoff.duration_at(attempt).unwrap();
+        let low = base.mul_f32(0.7).max(Duration::from_millis(100));
+        let high = base.mul_f32(1.3).min(Duration::from_secs(1));
+        assert!(first >= low && first <= high);
+    }
+}
+
+#[test]
+fn circuit_state_changes_the_schedule() {
+    let backoff = Backoff::new(8, Duration::from_millis(10), None);
+    let state = Arc::new(AtomicU8::new(CircuitAwareIter::CLOSED));
+    let mut iter = backoff.clone().into_iter_with_circuit_state(state.clone());
+
+    let base = backoff.duration_at(0).unwrap();
+    let duration = iter.next().unwrap().unwrap();
+    assert!(duration >= base.mul_f32(0.7));
+
+    state.store(CircuitAwareIter::HALF_OPEN, Ordering::Release);
+    let base = backoff.duration_at(1).unwrap();
+    let duration = iter.next().unwrap().unwrap();
+    assert!(duration <= base.mul_f32(1.3) / 2);
+    assert!(duration >= Duration::from_millis(10));
+    assert_eq!(iter.size_hint(), (1, Some(6)));
+
+    state.store(CircuitAwareIter::OPEN, Ordering::Release);
+    assert_eq!(iter.next(), Some(None));
+
+    state.store(CircuitAwareIter::CLOSED, Ordering::Release);
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.size_hint(), (0, Some(0)));
+
+    // The base delay is halved before clamping, so it never drops below min.
+    let mut backoff = Backoff::new(8, Duration::from_millis(10), None);
+    backoff.set_jitter(0.0);
+    let state = Arc::new(AtomicU8::new(CircuitAwareIter::HALF_OPEN));
+    let mut iter = backoff.into_iter_with_circuit_state(state);
+    assert_eq!(iter.next(), Some(Some(Duration::from_millis(10))));
+    assert_eq!(iter.next(), Some(Some(Duration::from_millis(10))));
+    assert_eq!(iter.next(), Some(Some(Duration::from_millis(20))));
+}
+
+#[derive(Debug)]
+struct Decreasing;
+
+impl Curve for Decreasing {
+    fn base_delay(&self, attempt: u32, _min: Duration, _max: Duration) -> Duration {
+        match attempt {
+            0 => Duration::from_millis(100),
+            _ => Duration::from_millis(20),
+        }
+    }
+}
+
+#[test]
+fn floor_policies() {
+    let min = Duration::from_millis(40);
+    let mut backoff = Backoff::new(3, min, Duration::from_secs(1));
+    backoff.set_curve(Decreasing);
+
+    let durations: Vec<_> = backoff.iter().flatten().collect();
+    assert_eq!(durations[1], min);
+
+    backoff.set_floor(Floor::Previous);
+    let durations: Vec<_> = backoff.iter().flatten().collect();
+    assert!(durations[1] >= durations[0]);
+
+    backoff.set_floor(Floor::None);
+    let durations: Vec<_> = backoff.iter().flatten().collect();
+    assert!(durations[1] >= Duration::from_millis(14) && durations[1] <= Duration::from_millis(26));
+}
+
+#[test]
+fn scale_attempts() {
+    let backoff = Backoff::new(4, Duration::from_millis(10), None);
+    assert_eq!(backoff.clone().multiply_attempts(2).iter().count(), 8);
+    assert_eq!(backoff.clone().divide_attempts(3).iter().count(), 1);
+    assert_eq!(backoff.clone().divide_attempts(8).iter().count(), 1);
+
+    let backoff = Backoff::new(u32::MAX, Duration::from_millis(10), None);
+    let len = backoff.multiply_attempts(2).iter().size_hint().0;
+    assert_eq!(len, u32::MAX as usize);
+}
+
+#[cfg(feature = "retry-compat")]
+#[test]
+fn retry_compat_matches_retry_crate() {
+    use exponential_backoff::retry_operation;
+    use retry::{delay::Fixed, OperationResult};
+
+    fn flaky(calls: &mut u32) -> OperationResult<u32, &'static str> {
+        *calls += 1;
+        match *calls {
+            1 | 2 => OperationResult::Retry("flaky"),
+            n => OperationResult::Ok(n),
+        }
+    }
+
+    let backoff = Backoff::new(3, Duration::from_millis(1), Duration::from_millis(5));
+    let mut ours = 0;
+    let mut theirs = 0;
+    let result = retry_operation(&backoff, || flaky(&mut ours));
+    let expected = retry::retry(Fixed::from_millis(1).take(2), || flaky(&mut theirs));
+    assert_eq!(result, expected);
+    assert_eq!(ours, theirs);
+
+    let result = retry_operation(&backoff, || Err::<(), _>("down")).unwrap_err();
+    let expected = retry::retry(Fixed::from_millis(1).take(2), || Err::<(), _>("down"));
+    assert_eq!(result.tries, expected.unwrap_err().tries);
+}
+
+#[test]
+fn aggressive_enough_for_availability() {
+    let min = Duration::from_millis(10);
+    assert!(!Backoff::new(9, min, None).is_aggressive_enough_for(0.999, 0.5));
+    assert!(Backoff::new(10, min, None).is_aggressive_enough_for(0.999, 0.5));
+    assert!(Backoff::new(1, min, None).is_aggressive_enough_for(0.9, 0.1));
+    assert!(!Backoff::new(0, min, None).is_aggressive_enough_for(0.9, 0.1));
+}
+
+#[test]
+fn time_to_ceiling() {
+    let min = Duration::from_millis(100);
+    let backoff = Backoff::new(10, min, Duration::from_millis(800));
+    assert_eq!(
+        backoff.time_to_ceiling(),
+        Duration::from_millis(100 + 200 + 400)
+    );
+
+    let backoff = Backoff::new(10, min, min);
+    assert_eq!(backoff.time_to_ceiling(), Duration::ZERO);
+
+    let backoff = Backoff::new(3, min, Duration::from_secs(10));
+    assert_eq!(backoff.time_to_ceiling(), Duration::MAX);
+
+    let backoff = Backoff::new(u32::MAX, min, None);
+    assert_eq!(backoff.time_to_ceiling(), Duration::MAX);
+}
+
+#[test]
+fn total_delay_excludes_last_attempt() {
+    let backoff = Backoff::new(5, Duration::from_millis(100), Duration::from_secs(1));
+    let expected: Duration = (0..4).map(|n| backoff.duration_at(n).unwrap()).sum();
+    assert_eq!(expected, Duration::from_millis(100 + 200 + 400 + 800));
+    assert_eq!(backoff.total_delay_excluding_last(), expected);
+
+    let backoff = Backoff::new(1, Duration::from_millis(100), None);
+    assert_eq!(backoff.total_delay_excluding_last(), Duration::ZERO);
+}
+
+#[test]
+fn next_nanos_matches_next() {
+    let mut backoff = Backoff::new(12, Duration::from_millis(10), Duration::from_secs(5));
+    backoff.set_jitter(0.0);
+    let mut durations = backoff.iter();
+    let mut nanos = durations.clone();
+    loop {
+        let expected = durations.next();
+        let actual = nanos.next_nanos();
+        assert_eq!(actual, expected.map(|d| d.map(|d| d.as_nanos() as u64)));
+        if actual.is_none() {
+            break;
+        }
+    }
+}
+
+#[test]
+fn attempt_timeouts() {
+    let backoff = Backoff::new(4, Duration::from_millis(100), None);
+    let timeout = Duration::from_secs(2);
+
+    let iter = backoff.clone().into_iter_with_attempt_timeout(timeout);
+    assert!(iter.map(|(_, t)| t).all(|t| t == timeout));
+
+    let mut iter = backoff.clone().into_iter_with_attempt_timeout(timeout);
+    iter.set_mode(TimeoutMode::RemainingBudget);
+    let timeouts: Vec<_> = iter.map(|(_, t)| t).collect();
+    assert_eq!(timeouts.len(), 4);
+    assert_eq!(timeouts[0], timeout / 4);
+    assert!(timeouts.windows(2).all(|pair| pair[1] < pair[0]));
+
+    // A delay budget doesn't shrink the timeouts, since it's not applied.
+    let mut backoff = backoff;
+    backoff.set_jitter(0.0);
+    let mut iter = backoff.clone().into_iter_with_attempt_timeout(timeout);
+    iter.set_mode(TimeoutMode::RemainingBudget);
+    let expected: Vec<_> = iter.map(|(_, t)| t).collect();
+    backoff.set_max_total_delay(Duration::from_secs(10));
+    let mut iter = backoff.into_iter_with_attempt_timeout(timeout);
+    iter.set_mode(TimeoutMode::RemainingBudget);
+    assert_eq!(iter.map(|(_, t)| t).collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn jittered_constant_varies_around_delay() {
+    let delay = Duration::from_millis(100);
+    let backoff = Backoff::jittered_constant(64, delay, 0.2);
+    assert_eq!(backoff.duration_at(10), Some(delay));
+
+    let mut varied = false;
+    for duration in backoff.iter().flatten() {
+        assert!(duration >= delay.mul_f32(0.8) && duration <= delay.mul_f32(1.2));
+        varied |= duration != delay;
+    }
+    assert!(varied);
+}
+
+#[test]
+fn equal_min_and_max_keep_jitter() {
+    let delay = Duration::from_millis(100);
+    let mut backoff = Backoff::new(64, delay, delay);
+    assert!(backoff.iter().flatten().any(|duration| duration != delay));
+
+    backoff.set_strict_bounds(true);
+    assert!(backoff.iter().flatten().all(|duration| duration == delay));
+}
+
+#[test]
+fn take_while_under_stops_at_limit() {
+    let mut backoff = Backoff::new(10, Duration::from_millis(100), None);
+    backoff.set_jitter_bias(-1.0);
+
+    // Without upward jitter the delays are 100ms, 200ms, 400ms, then at least
+    // 560ms.
+    let durations: Vec<_> = backoff
+        .iter()
+        .take_while_under(Duration::from_millis(500))
+        .collect();
+    assert_eq!(durations.len(), 4);
+    assert!(durations[..3].iter().all(Option::is_some));
+    assert_eq!(durations[3], None);
+}
+
+#[test]
+fn attempts_for_confidence() {
+    let backoff = Backoff::new(3, Duration::from_millis(10), None);
+    assert_eq!(backoff.attempts_for_confidence(0.5, 0.75), 2);
+    assert_eq!(backoff.attempts_for_confidence(0.5, 0.999), 10);
+    assert_eq!(backoff.attempts_for_confidence(0.9, 0.99), 2);
+    assert_eq!(backoff.attempts_for_confidence(0.9, 0.5), 1);
+}
+
+#[test]
+#[should_panic]
+fn attempts_for_confidence_rejects_certainty() {
+    let backoff = Backoff::new(3, Duration::from_millis(10), None);
+    backoff.attempts_for_confidence(1.0, 0.9);
+}
+
+#[test]
+fn named_policies() {
+    let backoff = Backoff::new(5, Duration::from_millis(100), Duration::from_secs(1));
+    assert_eq!(backoff.name(), None);
+
+    let named = backoff.clone().with_name("payments");
+    assert_eq!(named.name(), Some("payments"));
+    for attempt in 0..5 {
+        assert_eq!(named.duration_at(attempt), backoff.duration_at(attempt));
+    }
+    assert_eq!(named.expected_total_wait(), backoff.expected_total_wait());
+    assert_eq!(named, backoff);
+}
+
+#[test]
+fn incremental_growth_matches_schedule() {
+    let mut backoff = Backoff::new(200, Duration::from_millis(1), None);
+    backoff.set_jitter(0.0);
+    for (attempt, duration) in backoff.iter().enumerate() {
+        assert_eq!(duration, backoff.duration_at(attempt as u32));
+    }
+
+    backoff.set_factor(3);
+    backoff.set_max(Duration::from_secs(3600));
+    for (attempt, duration) in backoff.iter().enumerate() {
+        assert_eq!(duration, backoff.duration_at(attempt as u32));
+    }
+}
+
+#[test]
+fn sized_for_p99() {
+    let mut backoff = Backoff::new(3, Duration::from_millis(100), Duration::from_secs(1));
+    backoff.set_factor(1);
+    let target = Duration::from_secs(1);
+    let sized = backoff.sized_for_p99(target);
+
+    let mut totals: Vec<Duration> = (0..2000).map(|_| sized.iter().flatten().sum()).collect();
+    totals.sort();
+    let p99 = totals[totals.len() * 99 / 100];
+    assert!(p99 <= target.mul_f32(1.15) && p99 >= target.mul_f32(0.85));
+
+    let backoff = Backoff::new(3, Duration::from_secs(10), None);
+    assert_eq!(
+        backoff.sized_for_p99(Duration::from_secs(1)).iter().count(),
+        1
+    );
+}
+
+#[test]
+fn compare_jitter() {
+    let mut backoff = Backoff::new(6, Duration::from_millis(100), Duration::from_secs(2));
+    let comparison = backoff.compare_jitter(200);
+    assert_eq!(comparison.deterministic, backoff.expected_total_wait());
+    assert!(comparison.stddev > Duration::ZERO);
+    assert!(comparison.min < comparison.max);
+
+    backoff.set_jitter(0.0);
+    let comparison = backoff.compare_jitter(200);
+    assert_eq!(comparison.stddev, Duration::ZERO);
+    assert_eq!(comparison.min, comparison.deterministic);
+    assert_eq!(comparison.max, comparison.deterministic);
+}
+
+#[test]
+fn driven_by_closure() {
+    let backoff = Backoff::new(8, Duration::from_millis(10), None);
+    let start = Duration::from_millis(10);
+    let durations: Vec<_> = backoff
+        .driven_by(|state: BackoffState| match state.last_delay {
+            Some(delay) if delay < Duration::from_millis(40) => BackoffAction::Sleep(delay * 2),
+            _ => BackoffAction::Sleep(start),
+        })
+        .collect();
+    let expected = [10, 20, 40, 10, 20, 40, 10].map(|ms| Some(Duration::from_millis(ms)));
+    assert_eq!(&durations[..7], &expected);
+    assert_eq!(durations[7], None);
+
+    let durations: Vec<_> = backoff
+        .driven_by(|state| match state.attempt {
+            0 => BackoffAction::Retry,
+            _ => BackoffAction::GiveUp,
+        })
+        .collect();
+    assert_eq!(durations.len(), 2);
+    assert_eq!(durations[1], None);
+}
+
+#[test]
+fn jitter_is_effective() {
+    let mut backoff = Backoff::new(4, Duration::from_millis(100), None);
+    assert!(backoff.jitter_is_effective());
+
+    backoff.set_jitter(0.01);
+    assert!(!backoff.jitter_is_effective());
+
+    let backoff = Backoff::new(4, Duration::ZERO, None);
+    assert!(!backoff.jitter_is_effective());
+
+    let backoff = Backoff::new(1, Duration::from_millis(100), None);
+    assert!(!backoff.jitter_is_effective());
+}
+
+#[test]
+fn concurrency_decreases() {
+    let backoff = Backoff::new(6, Duration::from_millis(10), None);
+    let levels: Vec<_> = backoff
+        .iter()
+        .with_concurrency(16)
+        .map(|(_, c)| c)
+        .collect();
+    assert_eq!(levels, [16, 8, 4, 2, 1, 1]);
+
+    let mut iter = backoff.iter().with_concurrency(81);
+    iter.set_decay(3);
+    let levels: Vec<_> = iter.map(|(_, c)| c).collect();
+    assert_eq!(levels, [81, 27, 9, 3, 1, 1]);
+    assert!(levels.windows(2).all(|pair| pair[1] <= pair[0]));
+}
+
+#[test]
+fn assert_within_limits() {
+    let backoff = Backoff::new(4, Duration::from_millis(100), Duration::from_secs(1));
+    let max_step = Duration::from_millis(250);
+    let max_total = Duration::from_secs(3);
+    let violations = backoff.assert_within(max_step, max_total).unwrap_err();
+    // The third delay is 400ms, and 200ms plus jitter may exceed 250ms too.
+    assert_eq!(violations.len(), 2);
+    assert!(matches!(violations[0], Violation::Step { attempt: 1, .. }));
+    assert!(matches!(violations[1], Violation::Step { attempt: 2, .. }));
+
+    let violations = backoff
+        .assert_within(Duration::from_secs(1), Duration::from_millis(800))
+        .unwrap_err();
+    assert!(matches!(violations[..], [Violation::Total { .. }]));
+
+    assert!(backoff
+        .assert_within(Duration::from_secs(1), Duration::from_secs(2))
+        .is_ok());
+}
+
+#[test]
+fn equal_min_and_max_keep_band_with_growth() {
+    let delay = Duration::from_millis(100);
+    let backoff = Backoff::new(32, delay, delay);
+    for duration in backoff.iter().flatten() {
+        assert!(duration >= delay.mul_f32(0.7) && duration <= delay.mul_f32(1.3));
+    }
+}
+
+#[test]
+fn setters_do_not_affect_in_flight_iterators() {
+    let min = Duration::from_millis(100);
+    let max = Duration::from_secs(10);
+    let mut backoff = Backoff::new(6, min, max);
+    backoff.set_floor(Floor::Previous);
+    backoff.set_jitter_bias(0.5);
+
+    let mut iter = backoff.iter();
+    let first = iter.next().unwrap().unwrap();
+
+    backoff.set_floor(Floor::None);
+    backoff.set_jitter_bias(-1.0);
+    backoff.set_jitter(0.0);
+    backoff.set_factor(10);
+    backoff.set_min(Duration::from_secs(1));
+    backoff.set_max(Duration::from_secs(1));
+
+    // The iterator keeps growing from its own previous delay, with its
+    // original factor and bounds.
+    let mut previous = first;
+    for duration in iter.by_ref().take(4) {
+        let duration = duration.unwrap();
+        assert!(duration >= previous);
+        assert!(duration <= max);
+        previous = duration;
+    }
+    assert_eq!(iter.next(), Some(None));
+
+    // New iterators do pick up the changes.
+    let durations: Vec<_> = backoff.iter().flatten().collect();
+    assert!(durations.iter().all(|&d| d == Duration::from_secs(1)));
+}
+
+#[test]
+fn drain_sleeping_total() {
+    let mut backoff = Backoff::new(4, Duration::from_millis(5), Duration::from_secs(1));
+    backoff.set_jitter(0.0);
+    let iter = backoff.iter();
+    let expected: Duration = backoff.iter().flatten().sum();
+    let slept = iter.drain_sleeping();
+    assert!(slept >= expected);
+    assert!(slept < expected + Duration::from_millis(500));
+    assert_eq!(iter.count(), 4);
+}
+
+#[test]
+fn lifetime_max_attempts_across_resets() {
+    let mut backoff = Backoff::new(3, Duration::from_millis(10), None);
+    backoff.set_lifetime_max_attempts(6);
+    let mut iter = backoff.iter();
+
+    // Two full cycles, each succeeding on the second attempt.
+    for _ in 0..2 {
+        assert!(iter.next().unwrap().is_some());
+        assert!(iter.next().unwrap().is_some());
+        iter.reset();
+    }
+
+    // Three attempts remain in this cycle, but only two in total.
+    assert_eq!(iter.size_hint(), (2, Some(2)));
+    assert!(iter.next().unwrap().is_some());
+    assert_eq!(iter.next(), Some(None));
+    assert_eq!(iter.next(), None);
+
+    // Resetting doesn't bring it back.
+    iter.reset();
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.size_hint(), (0, Some(0)));
+}
+
+#[test]
+fn clones_reseed() {
+    let backoff = Backoff::new(32, Duration::from_secs(1), Duration::from_secs(1));
+    let mut iter = backoff.iter();
+    iter.next();
+    let clone = iter.clone();
+    let a: Vec<_> = iter.collect();
+    let b: Vec<_> = clone.collect();
+    assert_eq!(a.len(), b.len());
+    assert_eq!(a.len(), 31);
+    assert_ne!(a, b);
+}
+
+#[test]
+fn skip_to_ceiling() {
+    let max = Duration::from_secs(1);
+    let mut backoff = Backoff::new(8, Duration::from_millis(100), max);
+    backoff.set_jitter(0.0);
+    let mut iter = backoff.iter();
+    iter.skip_to_ceiling();
+    // 100ms, 200ms, 400ms and 800ms are skipped.
+    assert_eq!(iter.size_hint(), (4, Some(4)));
+    assert_eq!(iter.next(), Some(Some(max)));
+    assert_eq!(iter.next(), Some(Some(max)));
+
+    // A peeked item is dropped along with the rest of the skipped ones.
+    let mut iter = backoff.iter();
+    assert_eq!(iter.peek_next(), Some(Some(Duration::from_millis(100))));
+    iter.skip_to_ceiling();
+    assert_eq!(iter.attempt(), 4);
+    assert_eq!(iter.remaining(), 4);
+    assert_eq!(iter.next(), Some(Some(max)));
+
+    // A schedule which never reaches the max is left alone.
+    backoff.set_factor(1);
+    let mut iter = backoff.iter();
+    iter.skip_to_ceiling();
+    assert_eq!(iter.size_hint(), (8, Some(8)));
+}
+
+#[test]
+fn bytes_round_trip() {
+    let mut backoff = Backoff::new(5, Duration::from_millis(100), Duration::from_secs(10));
+    backoff.set_jitter(0.25);
+    backoff.set_factor(3);
+    let bytes = backoff.to_bytes();
+    assert_eq!(bytes.len(), Backoff::ENCODED_LEN);
+    let decoded = Backoff::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.to_bytes(), bytes);
+    assert_eq!(decoded.duration_at(0), backoff.duration_at(0));
+
+    // An unbounded max survives the round trip.
+    let backoff = Backoff::new(3, Duration::from_millis(100), None);
+    let decoded = Backoff::from_bytes(&backoff.to_bytes()).unwrap();
+    assert_eq!(decoded.duration_at(1), Some(Duration::from_millis(200)));
+}
+
+#[test]
+fn bytes_rejected() {
+    let bytes = Backoff::new(3, Duration::from_millis(100), None).to_bytes();
+    assert_eq!(
+        Backoff::from_bytes(&bytes[..10]).unwrap_err(),
+        BackoffError::InvalidLength {
+            expected: 26,
+            actual: 10
+        }
+    );
+
+    let mut jitter = bytes;
+    jitter[20..22].copy_from_slice(&1001u16.to_le_bytes());
+    assert!(matches!(
+        Backoff::from_bytes(&jitter),
+        Err(BackoffError::JitterOutOfRange { .. })
+    ));
+
+    let mut bounds = bytes;
+    bounds[12..20].copy_from_slice(&1u64.to_le_bytes());
+    assert_eq!(
+        Backoff::from_bytes(&bounds).unwrap_err(),
+        BackoffError::MinGreaterThanMax
+    );
+}
+
+#[test]
+fn describe() {
+    let backoff = Backoff::new(4, Duration::from_millis(100), Duration::from_secs(10));
+    assert_eq!(
+        backoff.describe(),
+        "Retries up to 3 times, waiting ~100ms, ~200ms, ~400ms (±30% jitter), capped at 10s."
+    );
+
+    let mut backoff = Backoff::new(5, Duration::from_millis(500), Duration::from_secs(1));
+    backoff.set_jitter(0.0);
+    assert_eq!(
+        backoff.describe(),
+        "Retries up to 4 times, waiting ~500ms, ~1s (x3), capped at 1s."
+    );
+
+    let backoff = Backoff::new(1, Duration::from_millis(100), None);
+    assert_eq!(backoff.describe(), "Doesn't retry.");
+
+    // Long schedules are summarized.
+    let mut backoff = Backoff::new(u32::MAX, Duration::from_millis(100), None);
+    backoff.set_jitter(0.0);
+    backoff.set_growth_mode(GrowthMode::Linear);
+    backoff.set_factor(1);
+    assert_eq!(
+        backoff.describe(),
+        "Retries up to 4294967294 times, waiting ~100ms, ~200ms, ~300ms, ~400ms, …, then up to \
+         ~429496729.4s."
+    );
+}
+
+#[test]
+fn initial_delay_before_first_attempt() {
+    let delay = Duration::from_millis(50);
+    let mut backoff = Backoff::new(3, Duration::from_millis(1), None);
+    backoff.set_initial_delay(delay);
+    let start = Instant::now();
+    let mut first_call = None;
+    let outcome = retry_ex(&backoff, RetryOptions::new(), || {
+        first_call.get_or_insert_with(|| start.elapsed());
+        Ok::<_, ()>(())
+    });
+    assert!(matches!(
+        outcome,
+        RetryOutcome::Succeeded { attempts: 1, .. }
+    ));
+    assert!(first_call.unwrap() >= delay);
+
+    // The iterator itself doesn't yield it.
+    backoff.set_jitter(0.0);
+    assert_eq!(backoff.iter().next(), Some(Some(Duration::from_millis(1))));
+}
+
+#[test]
+fn jitter_direction() {
+    let backoff = Backoff::new(200, Duration::from_secs(1), Duration::from_secs(1));
+    let directions: Vec<_> = backoff.iter().with_jitter_direction().collect();
+    assert!(directions.iter().any(|(_, o)| *o == cmp::Ordering::Greater));
+    assert!(directions.iter().any(|(_, o)| *o == cmp::Ordering::Less));
+    assert_eq!(directions.last(), Some(&(None, cmp::Ordering::Equal)));
+
+    let mut backoff = Backoff::new(4, Duration::from_millis(100), None);
+    backoff.set_jitter(0.0);
+    assert!(backoff
+        .iter()
+        .with_jitter_direction()
+        .all(|(_, o)| o == cmp::Ordering::Equal));
+}
+
+#[test]
+fn merge_partial_overrides() {
+    let mut base = Backoff::new(5, Duration::from_millis(100), Duration::from_secs(10));
+    base.set_jitter(0.0);
+    let merged = base.merge(&PartialBackoff::new().factor(3));
+    assert_eq!(merged.iter().count(), 5);
+    assert_eq!(merged.duration_at(1), Some(Duration::from_millis(300)));
+    assert_eq!(merged.duration_at(3), Some(Duration::from_millis(2700)));
+    assert_eq!(merged.iter().nth(1), Some(Some(Duration::from_millis(300))));
+
+    // The base is left untouched, and an empty override changes nothing.
+    assert_eq!(base.duration_at(1), Some(Duration::from_millis(200)));
+    let merged = base.merge(&PartialBackoff::new());
+    assert_eq!(merged.to_bytes(), base.to_bytes());
+}
+
+#[test]
+fn check_invariants_advisories() {
+    let delay = Duration::from_millis(100);
+    let mut backoff = Backoff::new(3, delay, delay);
+    backoff.set_strict_bounds(true);
+    assert_eq!(
+        backoff.check_invariants(),
+        Ok(vec![Advisory::IneffectiveJitter])
+    );
+
+    backoff.set_jitter(0.0);
+    assert_eq!(backoff.check_invariants(), Ok(vec![]));
+
+    let backoff = Backoff::new(3, delay, Duration::from_secs(1));
+    assert_eq!(backoff.check_invariants(), Ok(vec![]));
+
+    let backoff = Backoff::new(3, Duration::from_secs(2), Duration::from_secs(1));
+    assert_eq!(
+        backoff.check_invariants(),
+        Err(BackoffError::MinGreaterThanMax)
+    );
+}
+
+#[test]
+fn builder_validates() {
+    let backoff = Backoff::builder().build().unwrap();
+    assert_eq!(backoff, Backoff::default());
+
+    let backoff = Backoff::builder()
+        .attempts(5)
+        .min(Duration::from_millis(50))
+        .max(None)
+        .jitter(0.0)
+        .factor(3)
+        .growth_mode(GrowthMode::Linear)
+        .build()
+        .unwrap();
+    let mut expected = Backoff::new(5, Duration::from_millis(50), None);
+    expected.set_jitter(0.0);
+    expected.set_factor(3);
+    expected.set_growth_mode(GrowthMode::Linear);
+    assert_eq!(backoff, expected);
+
+    assert_eq!(
+        Backoff::builder().jitter(1.5).build(),
+        Err(BackoffError::JitterOutOfRange { value: 1.5 })
+    );
+    assert_eq!(
+        Backoff::builder().min(Duration::from_secs(20)).build(),
+        Err(BackoffError::MinGreaterThanMax)
+    );
+    assert_eq!(
+        Backoff::builder().factor(0).build(),
+        Err(BackoffError::FactorTooSmall { value: 0 })
+    );
+}
+
+#[test]
+fn partition_spreads_first_delays() {
+    let mut backoff = Backoff::new(4, Duration::from_millis(100), None);
+    backoff.set_jitter(0.0);
+    let first: Vec<_> = (0..4)
+        .map(|worker| backoff.partition(worker, 4).next().unwrap().unwrap())
+        .collect();
+    let expected = [100, 125, 150, 175].map(Duration::from_millis);
+    assert_eq!(first, expected);
+
+    // Later delays keep the regular schedule.
+    let mut iter = backoff.partition(3, 4);
+    iter.next();
+    assert_eq!(iter.next(), Some(Some(Duration::from_millis(200))));
+}
+
+#[test]
+#[should_panic]
+fn partition_rejects_out_of_range_worker() {
+    let backoff = Backoff::new(4, Duration::from_millis(100), None);
+    backoff.partition(4, 4);
+}
+
+#[test]
+fn for_target_rate() {
+    let (clients, target_rps) = (1000, 50.0);
+    let backoff = Backoff::for_target_rate(clients, target_rps);
+
+    // Every client fails at the same moment, then retries on its own schedule.
+    let mut retries: Vec<Duration> = (0..clients)
+        .flat_map(|_| {
+            backoff
+                .iter()
+                .flatten()
+                .scan(Duration::ZERO, |sent_at, delay| {
+                    *sent_at += delay;
+                    Some(*sent_at)
+                })
+        })
+        .collect();
+    retries.sort();
+    assert_eq!(retries.len(), clients as usize * 2);
+
+    // The combined rate since the failure never exceeds the target.
+    for (sent, sent_at) in retries.iter().enumerate() {
+        let rate = (sent + 1) as f64 / sent_at.as_secs_f64();
+        assert!(rate <= target_rps, "{rate} rps after {sent_at:?}");
+    }
+}
+
+#[test]
+fn retry_while_healthy_stops_when_flag_clears() {
+    let backoff = Backoff::new(10, Duration::from_millis(1), Duration::from_millis(2));
+    let healthy = Arc::new(AtomicBool::new(true));
+    let mut calls = 0;
+    let outcome = retry_while_healthy(&backoff, healthy.clone(), || {
+        calls += 1;
+        if calls == 3 {
+            healthy.store(false, Ordering::Release);
+        }
+        Err::<(), _>(calls)
+    });
+    assert_eq!(calls, 3);
+    assert!(matches!(
+        outcome,
+        RetryOutcome::Aborted {
+            reason: AbortReason::Unhealthy(3)
+        }
+    ));
+}
+
+#[test]
+fn keepalive_fires_while_sleeping() {
+    let mut backoff = Backoff::new(2, Duration::from_millis(2500), None);
+    backoff.set_jitter(0.0);
+    let mut fired = 0;
+    let start = Instant::now();
+    let mut iter = backoff
+        .iter()
+        .with_keepalive(Duration::from_secs(1), || fired += 1);
+    assert_eq!(iter.next(), Some(Some(Duration::from_millis(2500))));
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_eq!(iter.next(), Some(None));
+    assert_eq!(iter.next(), None);
+    drop(iter);
+    assert!(start.elapsed() >= Duration::from_millis(2500));
+    assert_eq!(fired, 2);
+}
+
+#[test]
+fn random_budget_within_bounds() {
+    let backoff = Backoff::new(5, Duration::from_millis(100), Duration::from_secs(10));
+    let total = backoff.expected_total_wait();
+    assert_eq!(total, Duration::from_millis(1500));
+    let mut rng = fastrand::Rng::with_seed(7);
+    let budgets: Vec<_> = (0..200).map(|_| backoff.random_budget(&mut rng)).collect();
+    for budget in &budgets {
+        assert!(*budget >= total.mul_f32(0.7) && *budget <= total.mul_f32(1.3));
+    }
+    assert!(budgets.iter().any(|budget| *budget != budgets[0]));
+}
+
+#[test]
+fn compiled_to_backoff() {
+    let schedule = [1, 2, 4].map(Duration::from_secs);
+    let compiled = CompiledBackoff::from_schedule(schedule);
+    let backoff = compiled.to_backoff().unwrap();
+    let mut expected = Backoff::new(4, Duration::from_secs(1), Duration::from_secs(4));
+    expected.set_jitter(0.0);
+    assert_eq!(backoff.to_bytes(), expected.to_bytes());
+    assert_eq!(backoff.compile(), compiled);
+
+    // Levelling off at the max is still clean.
+    let schedule = [3, 9, 27, 30, 30].map(Duration::from_secs);
+    let compiled = CompiledBackoff::from_schedule(schedule);
+    assert_eq!(compiled.to_backoff().unwrap().compile(), compiled);
+
+    let irregular = CompiledBackoff::from_schedule([1, 2, 5].map(Duration::from_secs));
+    assert!(irregular.to_backoff().is_none());
+    let collapsing = CompiledBackoff::from_schedule([1, 0].map(Duration::from_secs));
+    assert!(collapsing.to_backoff().is_none());
+    assert!(CompiledBackoff::from_schedule([]).to_backoff().is_none());
+}
+
+#[test]
+#[cfg(feature = "ffi")]
+fn ffi_round_trip() {
+    use exponential_backoff::ffi::*;
+    use std::ptr;
+
+    let handle = exponential_backoff_new(3, 100_000_000, u64::MAX);
+    assert!(!handle.is_null());
+    let mut nanos = 0;
+    unsafe {
+        assert_eq!(
+            exponential_backoff_next(handle, &mut nanos),
+            EXPONENTIAL_BACKOFF_DELAY
+        );
+        assert!((100_000_000..=130_000_000).contains(&nanos));
+        assert_eq!(
+            exponential_backoff_next(handle, &mut nanos),
+            EXPONENTIAL_BACKOFF_DELAY
+        );
+        assert_eq!(
+            exponential_backoff_next(handle, &mut nanos),
+            EXPONENTIAL_BACKOFF_LAST
+        );
+        assert_eq!(
+            exponential_backoff_next(handle, &mut nanos),
+            EXPONENTIAL_BACKOFF_DONE
+        );
+        assert_eq!(
+            exponential_backoff_next(handle, ptr::null_mut()),
+            EXPONENTIAL_BACKOFF_DONE
+        );
+        assert_eq!(
+            exponential_backoff_next(ptr::null_mut(), &mut nanos),
+            EXPONENTIAL_BACKOFF_DONE
+        );
+        exponential_backoff_free(handle);
+        exponential_backoff_free(ptr::null_mut());
+    }
+    assert!(exponential_backoff_new(3, 2, 1).is_null());
+}
+
+#[test]
+fn smoothing() {
+    let mut backoff = Backoff::new(6, Duration::from_millis(100), Duration::from_secs(10));
+    backoff.set_jitter(0.0);
+    let unsmoothed: Vec<_> = backoff.iter().collect();
+
+    backoff.set_smoothing(1.0);
+    assert_eq!(backoff.iter().collect::<Vec<_>>(), unsmoothed);
+
+    backoff.set_smoothing(0.1);
+    let smoothed: Vec<_> = backoff.iter().flatten().collect();
+    assert_eq!(smoothed[0], Duration::from_millis(100));
+    assert_eq!(smoothed[1], Duration::from_millis(110));
+    let unsmoothed: Vec<_> = unsmoothed.into_iter().flatten().collect();
+    for i in 1..smoothed.len() {
+        assert!(smoothed[i] >= smoothed[i - 1]);
+        assert!(smoothed[i] - smoothed[i - 1] < (unsmoothed[i] - unsmoothed[i - 1]) / 2);
+    }
+}
+
+#[test]
+#[should_panic]
+fn smoothing_out_of_range() {
+    let mut backoff = Backoff::new(6, Duration::from_millis(100), None);
+    backoff.set_smoothing(1.5);
+}
+
+#[test]
+fn control_sequence() {
+    let mut backoff = Backoff::new(3, Duration::from_millis(100), None);
+    backoff.set_jitter(0.0);
+    let steps: Vec<_> = backoff.control().collect();
+    assert_eq!(
+        steps,
+        [
+            Control::Try,
+            Control::TryAfter(Duration::from_millis(100)),
+            Control::TryAfter(Duration::from_millis(200)),
+            Control::Done,
+        ]
+    );
+
+    let backoff = Backoff::new(0, Duration::from_millis(100), None);
+    assert_eq!(backoff.control().collect::<Vec<_>>(), [Control::Done]);
+}
+
+#[test]
+fn seeded_iterators_are_reproducible() {
+    let backoff = Backoff::new(16, Duration::from_millis(100), Duration::from_secs(10));
+    let first: Vec<_> = backoff.iter_seeded(7).collect();
+    let second: Vec<_> = backoff.iter().with_seed(7).collect();
+    assert_eq!(first, second);
+    assert_ne!(first, backoff.iter_seeded(8).collect::<Vec<_>>());
+}
+
+#[test]
+fn attempt_timeouts_sliced_by_deadline() {
+    let mut backoff = Backoff::new(4, Duration::from_millis(10), None);
+    backoff.set_jitter(0.0);
+    let deadline = Instant::now() + Duration::from_millis(400);
+    let mut iter = backoff.into_iter_with_attempt_timeout(Duration::MAX);
+    iter.set_mode(TimeoutMode::Deadline(deadline));
+
+    // Every attempt uses its whole timeout, then sleeps.
+    let mut timeouts = vec![];
+    for (duration, timeout) in iter {
+        assert!(timeout <= Duration::from_millis(100));
+        timeouts.push(timeout);
+        thread::sleep(timeout + duration.unwrap_or_default());
+    }
+    assert_eq!(timeouts.len(), 4);
+    assert!(timeouts.windows(2).all(|pair| pair[1] < pair[0]));
+}
+
+#[test]
+fn retry_stats_totals() {
+    let mut backoff = Backoff::new(4, Duration::from_millis(1), None);
+    backoff.set_jitter(0.0);
+    let (outcome, stats) = retry_with_stats(&backoff, RetryOptions::new(), || Err::<(), _>(()));
+    assert!(matches!(
+        outcome,
+        RetryOutcome::Exhausted { attempts: 4, .. }
+    ));
+    assert_eq!(stats.attempts, 4);
+    assert_eq!(stats.scheduled_total, Duration::from_millis(1 + 2 + 4));
+    assert_eq!(stats.realized_total, stats.scheduled_total);
+
+    backoff.set_jitter(0.5);
+    let differs = (0..20).any(|_| {
+        let (_, stats) = retry_with_stats(&backoff, RetryOptions::new(), || Err::<(), _>(()));
+        assert_eq!(stats.scheduled_total, Duration::from_millis(1 + 2 + 4));
+        stats.realized_total != stats.scheduled_total
+    });
+    assert!(differs);
+}
+
+#[test]
+fn try_set_jitter() {
+    let mut backoff = Backoff::new(3, Duration::from_millis(100), None);
+    assert_eq!(backoff.try_set_jitter(0.5), Ok(()));
+    assert_eq!(backoff.try_set_jitter(1.0), Ok(()));
+    for jitter in [-0.1, 1.1, f32::INFINITY, f32::NEG_INFINITY] {
+        assert_eq!(
+            backoff.try_set_jitter(jitter),
+            Err(BackoffError::JitterOutOfRange { value: jitter })
+        );
+    }
+    assert!(matches!(
+        backoff.try_set_jitter(f32::NAN),
+        Err(BackoffError::JitterOutOfRange { value }) if value.is_nan()
+    ));
+}
+
+#[test]
+#[should_panic]
+fn set_jitter_rejects_nan() {
+    let mut backoff = Backoff::new(3, Duration::from_millis(100), None);
+    backoff.set_jitter(f32::NAN);
+}
+
+#[test]
+fn fan_out_spreads_cohort() {
+    let mut backoff = Backoff::new(4, Duration::from_millis(100), None);
+    backoff.set_jitter(0.0);
+    let cohort = backoff.fan_out(10);
+    assert_eq!(cohort.len(), 10);
+    let first: Vec<_> = cohort
+        .into_iter()
+        .map(|mut iter| iter.next().unwrap().unwrap())
+        .collect();
+    for (i, duration) in first.iter().enumerate() {
+        assert_eq!(*duration, Duration::from_millis(100 + 10 * i as u64));
+    }
+
+    // With jitter the cohort still spreads across the first interval.
+    let backoff = Backoff::new(4, Duration::from_millis(100), None);
+    let first: Vec<_> = backoff
+        .fan_out(10)
+        .into_iter()
+        .map(|mut iter| iter.next().unwrap().unwrap())
+        .collect();
+    let earliest = first.iter().min().unwrap();
+    let latest = first.iter().max().unwrap();
+    assert!(*latest - *earliest > Duration::from_millis(50));
+}
+
+#[test]
+fn jitter_strategies() {
+    let min = Duration::from_millis(100);
+    let max = Duration::from_secs(100);
+    let mut backoff = Backoff::new(64, min, max);
+    backoff.set_floor(Floor::None);
+
+    backoff.set_jitter_strategy(JitterStrategy::Full);
+    for (attempt, duration) in backoff.iter().flatten().enumerate() {
+        assert!(duration <= backoff.duration_at(attempt as u32).unwrap());
+    }
+
+    backoff.set_jitter_strategy(JitterStrategy::Equal);
+    for (attempt, duration) in backoff.iter().flatten().enumerate() {
+        let scheduled = backoff.duration_at(attempt as u32).unwrap();
+        assert!(duration >= scheduled / 2 && duration <= scheduled);
+    }
+
+    // Each decorrelated delay depends on the one before it.
+    backoff.set_jitter_strategy(JitterStrategy::Decorrelated);
+    let durations: Vec<_> = backoff.iter().flatten().collect();
+    assert!(durations[0] >= min && durations[0] <= min * 3);
+    for pair in durations.windows(2) {
+        assert!(pair[1] >= min && pair[1] <= pair[0] * 3);
+    }
+    assert!(durations.iter().any(|d| *d > min * 3));
+
+    // Absolute jitter is the same size for every delay.
+    let spread = Duration::from_millis(250);
+    backoff.set_jitter_strategy(JitterStrategy::Absolute(spread));
+    for (attempt, duration) in backoff.iter().flatten().enumerate() {
+        let scheduled = backoff.duration_at(attempt as u32).unwrap();
+        assert!(duration >= scheduled.saturating_sub(spread) && duration <= scheduled + spread);
+    }
+    let scheduled = backoff.duration_at(0).unwrap();
+    let first: Vec<_> = (0..64)
+        .map(|_| backoff.iter().next().unwrap().unwrap())
+        .collect();
+    assert!(first.iter().any(|d| *d < scheduled));
+    assert!(first.iter().any(|d| *d > scheduled));
+
+    // It's still clamped between the floor and the max.
+    backoff.set_floor(Floor::Min);
+    for duration in backoff.iter().flatten() {
+        assert!(duration >= min && duration <= max);
+    }
+}
+
+#[test]
+fn full_downward_jitter_never_lengthens() {
+    let min = Duration::from_millis(100);
+    let mut backoff = Backoff::new(16, min, Duration::from_secs(10));
+    backoff.set_jitter_strategy(JitterStrategy::FullDownward);
+    let mut shortened = false;
+    for seed in 0..200 {
+        for (attempt, duration) in backoff.iter_seeded(seed).flatten().enumerate() {
+            let scheduled = backoff.duration_at(attempt as u32).unwrap();
+            assert!(duration >= min && duration <= scheduled);
+            shortened |= duration < scheduled;
+        }
+    }
+    assert!(shortened);
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn stream_sleeps_between_attempts() {
+    use futures_core::Stream;
+    use std::{future::poll_fn, pin::Pin, sync::Mutex};
+
+    let mut backoff = Backoff::new(3, Duration::from_millis(1), None);
+    backoff.set_jitter(0.0);
+    let slept = Arc::new(Mutex::new(vec![]));
+    let recorded = slept.clone();
+    let mut stream = backoff.stream_with(move |duration| {
+        recorded.lock().unwrap().push(duration);
+        tokio::time::sleep(duration)
+    });
+
+    let mut attempts = 0;
+    while poll_fn(|cx| Pin::new(&mut stream).poll_next(cx))
+        .await
+        .is_some()
+    {
+        attempts += 1;
+    }
+    assert_eq!(attempts, 3);
+    let expected = [1, 2].map(Duration::from_millis);
+    assert_eq!(*slept.lock().unwrap(), expected);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn tokio_stream() {
+    use futures_core::Stream;
+    use std::{future::poll_fn, pin::Pin};
+
+    let backoff = Backoff::new(3, Duration::from_millis(10), None);
+    let mut stream = backoff.stream();
+    let start = Instant::now();
+    let mut attempts = 0;
+    while poll_fn(|cx| Pin::new(&mut stream).poll_next(cx))
+        .await
+        .is_some()
+    {
+        attempts += 1;
+    }
+    assert_eq!(attempts, 3);
+    assert!(start.elapsed() >= Duration::from_millis(24));
+}
+
+#[test]
+fn exact_size() {
+    let backoff = Backoff::new(5, Duration::from_millis(100), None);
+    let mut iter = backoff.iter();
+    let mut durations = Vec::with_capacity(iter.len());
+    for remaining in (0..5).rev() {
+        durations.push(iter.next().unwrap());
+        assert_eq!(iter.len(), remaining);
+        assert_eq!(iter.size_hint(), (remaining, Some(remaining)));
+    }
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.len(), 0);
+    assert_eq!(durations.len(), 5);
+
+    let backoff = Backoff::new(0, Duration::from_millis(100), None);
+    assert_eq!(backoff.iter().len(), 0);
+}
+
+#[test]
+fn max_total_delay_budget() {
+    let mut backoff = Backoff::new(10, Duration::from_millis(100), None);
+    backoff.set_jitter(0.0);
+    backoff.set_max_total_delay(Duration::from_millis(1000));
+    assert_eq!(
+        backoff.total_delay_budget(),
+        Some(Duration::from_millis(1000))
+    );
+
+    // Plain iterators ignore the budget, and know their length.
+    assert_eq!(backoff.iter().len(), 10);
+    assert_eq!(backoff.iter().count(), 10);
+
+    // 100ms + 200ms + 400ms fit, adding 800ms would not.
+    let mut iter = backoff.iter().within_budget();
+    assert_eq!(iter.size_hint(), (1, Some(10)));
+    let durations: Vec<_> = iter.by_ref().collect();
+    let expected = [Some(100), Some(200), Some(400), None].map(|d| d.map(Duration::from_millis));
+    assert_eq!(durations, expected);
+    assert_eq!(iter.size_hint(), (0, Some(0)));
+
+    // The max attempts still apply when they're hit first.
+    backoff.set_max_total_delay(Duration::from_secs(200));
+    assert_eq!(backoff.iter().within_budget().count(), 10);
+
+    // The budget counts the jittered delays.
+    let mut backoff = Backoff::new(64, Duration::from_millis(50), Duration::from_millis(100));
+    backoff.set_max_total_delay(Duration::from_millis(500));
+    let total: Duration = backoff.iter().within_budget().flatten().sum();
+    assert!(total <= Duration::from_millis(500));
+
+    // The retry helpers apply the budget.
+    let mut slept = Duration::ZERO;
+    let _ = retry_with(&backoff, |d| slept += d, || Err::<(), _>(()));
+    assert!(slept <= Duration::from_millis(500));
+}
+
+#[test]
+fn growth_modes() {
+    let mut backoff = Backoff::new(5, Duration::from_millis(100), Duration::from_secs(10));
+    backoff.set_jitter(0.0);
+    backoff.set_factor(3);
+
+    backoff.set_growth_mode(GrowthMode::Linear);
+    let durations: Vec<_> = backoff.iter().flatten().collect();
+    assert_eq!(durations, [100, 400, 700, 1000].map(Duration::from_millis));
+    assert_eq!(backoff.expected_total_wait(), Duration::from_millis(2200));
+
+    backoff.set_growth_mode(GrowthMode::Constant);
+    let durations: Vec<_> = backoff.iter().flatten().collect();
+    assert_eq!(durations, [Duration::from_millis(100); 4]);
+    assert_eq!(backoff.expected_total_wait(), Duration::from_millis(400));
+
+    // Jitter still applies.
+    backoff.set_jitter(0.3);
+    backoff.set_floor(Floor::None);
+    let durations: Vec<_> = backoff.iter().flatten().collect();
+    assert!(durations.iter().any(|d| *d != Duration::from_millis(100)));
+}
+
+#[test]
+fn fractional_factor() {
+    let mut backoff = Backoff::new(5, Duration::from_secs(1), Duration::from_secs(60));
+    backoff.set_jitter(0.0);
+    backoff.set_factor_f64(1.5);
+    let durations: Vec<_> = backoff.iter().flatten().collect();
+    let expected = [1_000, 1_500, 2_250, 3_375].map(Duration::from_millis);
+    assert_eq!(durations, expected);
+    assert_eq!(backoff.expected_total_wait(), durations.iter().sum());
+
+    // Still clamped to the max.
+    backoff.set_max(Duration::from_secs(2));
+    let durations: Vec<_> = backoff.iter().flatten().collect();
+    assert_eq!(durations[2..], [Duration::from_secs(2); 2]);
+
+    // Setting an integer factor replaces the fractional one.
+    backoff.set_factor(2);
+    let mut other = Backoff::new(5, Duration::from_secs(1), Duration::from_secs(2));
+    other.set_jitter(0.0);
+    assert_eq!(backoff, other);
+
+    // A factor barely above one grows for a long time before it saturates.
+    let mut backoff = Backoff::new(u32::MAX, Duration::from_millis(1), None);
+    backoff.set_factor_f64(1.000_000_1);
+    assert_eq!(backoff.expected_total_wait(), Duration::MAX);
+    assert_eq!(backoff.max_total_delay(), Duration::MAX);
+    assert_eq!(backoff.time_to_ceiling(), Duration::MAX);
+
+    // Long ramps are summed close to the geometric series.
+    let mut backoff = Backoff::new(10_000_001, Duration::from_millis(1), None);
+    backoff.set_factor_f64(1.000_000_1);
+    let expected = 0.001 * (1.000_000_1f64.powi(10_000_000) - 1.0) / 0.000_000_1;
+    let total = backoff.expected_total_wait().as_secs_f64();
+    assert!((total / expected - 1.0).abs() < 1e-4);
+
+    // A zero min stays zero, even once the factor overflows to infinity.
+    let mut backoff = Backoff::new(2000, Duration::ZERO, None);
+    backoff.set_factor_f64(2.0);
+    assert_eq!(backoff.duration_at(1500), Some(Duration::ZERO));
+    assert_eq!(backoff.expected_total_wait(), Duration::ZERO);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_round_trip() {
+    let backoff = Backoff::default();
+    let json = serde_json::to_string(&backoff).unwrap();
+    assert_eq!(
+        json,
+        r#"{"max_attempts":3,"min":100,"max":10000,"jitter":0.3,"factor":2}"#
+    );
+    assert_eq!(serde_json::from_str::<Backoff>(&json).unwrap(), backoff);
+
+    let unbounded = Backoff::new(5, Duration::from_millis(100), None);
+    let json = serde_json::to_string(&unbounded).unwrap();
+    assert_eq!(serde_json::from_str::<Backoff>(&json).unwrap(), unbounded);
+
+    let json = r#"{"max_attempts":3,"min":100,"max":10000,"jitter":1.5,"factor":2}"#;
+    assert!(serde_json::from_str::<Backoff>(json).is_err());
+    let json = r#"{"max_attempts":3,"min":100,"max":10,"jitter":0.3,"factor":2}"#;
+    assert!(serde_json::from_str::<Backoff>(json).is_err());
+}
+
+#[test]
+fn retry_until_ok() {
+    let backoff = Backoff::new(5, Duration::from_millis(1), Duration::from_millis(2));
+    let mut calls = 0;
+    let result = retry(&backoff, || {
+        calls += 1;
+        if calls < 3 {
+            Err(calls)
+        } else {
+            Ok("done")
+        }
+    });
+    assert_eq!(result, Ok("done"));
+    assert_eq!(calls, 3);
+
+    let mut calls = 0;
+    let result: Result<(), _> = retry(&backoff, || {
+        calls += 1;
+        Err(calls)
+    });
+    assert_eq!(result, Err(5));
+}
+
+#[test]
+fn retry_with_custom_sleep() {
+    let mut backoff = Backoff::new(4, Duration::from_millis(100), None);
+    backoff.set_jitter(0.0);
+    let mut slept = vec![];
+    let result: Result<(), _> = retry_with(&backoff, |d| slept.push(d), || Err(()));
+    assert_eq!(result, Err(()));
+    assert_eq!(slept, [100, 200, 400].map(Duration::from_millis));
+
+    let backoff = Backoff::new(0, Duration::from_millis(100), None);
+    let mut calls = 0;
+    let result = retry_with(
+        &backoff,
+        |_| unreachable!(),
+        || {
+            calls += 1;
+            Err::<(), _>(())
+        },
+    );
+    assert_eq!((result, calls), (Err(()), 1));
+
+    // Without `std` the delays come from a seeded iterator instead.
+    let mut backoff = Backoff::new(4, Duration::from_millis(100), None);
+    backoff.set_initial_delay(Duration::from_millis(10));
+    let mut slept = vec![];
+    let result: Result<(), _> =
+        retry_with_iter(backoff.iter_seeded(7), |d| slept.push(d), || Err(()));
+    assert_eq!(result, Err(()));
+    let expected: Vec<_> = backoff.iter_seeded(7).flatten().collect();
+    assert_eq!(slept[0], Duration::from_millis(10));
+    assert_eq!(slept[1..], expected);
+}
+
+#[test]
+fn reset_restarts_with_same_rng() {
+    let mut backoff = Backoff::new(4, Duration::from_millis(100), None);
+    backoff.set_max_total_delay(Duration::from_millis(500));
+    let run = |iter: &mut BudgetedIter| iter.by_ref().collect::<Vec<_>>();
+
+    let mut iter = backoff.iter_seeded(7).within_budget();
+    let first = run(&mut iter);
+    assert_eq!(iter.next(), None);
+    iter.reset();
+    let second = run(&mut iter);
+    assert_eq!(second.len(), 3);
+    assert_eq!(second.last(), Some(&None));
+    assert_ne!(first, second);
+
+    // The generator carried on rather than being reseeded.
+    let mut replay = backoff.iter_seeded(7).within_budget();
+    assert_eq!(run(&mut replay), first);
+    replay.reset();
+    assert_eq!(run(&mut replay), second);
+}
+
+#[test]
+fn jitter_applies_to_huge_delays() {
+    for min in [
+        Duration::from_secs(1_000_000),
+        Duration::from_secs(u64::MAX / 8),
+    ] {
+        let mut backoff = Backoff::new(8, min, None);
+        backoff.set_floor(Floor::None);
+        let scheduled = backoff.duration_at(0).unwrap();
+        let durations: Vec<_> = (0..16)
+            .map(|_| backoff.iter().next().unwrap().unwrap())
+            .collect();
+        assert!(durations.iter().any(|d| *d != scheduled));
+        for duration in durations {
+            assert!(duration >= scheduled.mul_f64(0.7) && duration <= scheduled.mul_f64(1.3));
+        }
+    }
+}
+
+#[test]
+fn enumerated_attempts() {
+    let mut backoff = Backoff::new(4, Duration::from_millis(100), None);
+    backoff.set_jitter(0.0);
+    let items: Vec<_> = backoff.iter_enumerated().collect();
+    assert_eq!(
+        items,
+        [
+            (0, Some(Duration::from_millis(100))),
+            (1, Some(Duration::from_millis(200))),
+            (2, Some(Duration::from_millis(400))),
+            (3, None),
+        ]
+    );
+}
+
+#[test]
+fn peek_next() {
+    let backoff = Backoff::new(3, Duration::from_millis(100), None);
+    let mut iter = backoff.iter();
+    let peeked = iter.peek_next();
+    assert!(matches!(peeked, Some(Some(_))));
+    assert_eq!(iter.peek_next(), peeked);
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.next(), peeked);
+    assert_eq!(iter.len(), 2);
+
+    iter.next();
+    assert_eq!(iter.peek_next(), Some(None));
+    assert_eq!(iter.len(), 1);
+    assert_eq!(iter.next(), Some(None));
+    assert_eq!(iter.peek_next(), None);
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+fn attempt_and_remaining() {
+    let backoff = Backoff::new(3, Duration::from_millis(100), None);
+    let mut iter = backoff.iter();
+    assert_eq!((iter.attempt(), iter.remaining()), (0, 3));
+    iter.next();
+    assert_eq!((iter.attempt(), iter.remaining()), (1, 2));
+
+    // Peeking doesn't count as an attempt.
+    iter.peek_next();
+    assert_eq!((iter.attempt(), iter.remaining()), (1, 2));
+    iter.next();
+    iter.next();
+    assert_eq!((iter.attempt(), iter.remaining()), (3, 0));
+    iter.next();
+    assert_eq!((iter.attempt(), iter.remaining()), (3, 0));
+}
+
+#[test]
+fn total_delay_bounds() {
+    let backoff = Backoff::new(4, Duration::from_millis(100), Duration::from_secs(10));
+    assert_eq!(backoff.max_total_delay(), Duration::from_millis(903));
+    assert_eq!(backoff.min_total_delay(), Duration::from_millis(526));
+    for _ in 0..100 {
+        let total: Duration = backoff.iter().flatten().sum();
+        assert!(total >= backoff.min_total_delay() && total <= backoff.max_total_delay());
+    }
+
+    // Unbounded schedules saturate instead of overflowing.
+    let mut backoff = Backoff::new(u32::MAX, Duration::from_secs(1), None);
+    assert_eq!(backoff.max_total_delay(), Duration::MAX);
+    backoff.set_max_total_delay(Duration::from_secs(60));
+    assert_eq!(backoff.max_total_delay(), Duration::from_secs(60));
+}
+
+#[test]
+fn deadlines_accumulate_delays() {
+    let backoff = Backoff::new(4, Duration::from_millis(100), Duration::from_secs(10));
+    let start = Instant::now();
+    let delays: Vec<_> = backoff.iter_seeded(7).collect();
+    let deadlines: Vec<_> = backoff.iter_seeded(7).deadlines(start).collect();
+    assert_eq!(deadlines.len(), 4);
+    assert_eq!(deadlines[3], None);
+
+    let mut expected = start;
+    for (deadline, delay) in deadlines.iter().zip(&delays).take(3) {
+        expected += delay.unwrap();
+        assert_eq!(*deadline, Some(expected));
+    }
+    assert_eq!(backoff.deadlines(start).count(), 4);
+
+    // A deadline too far out to represent ends the iterator.
+    let backoff = Backoff::new(100, Duration::from_secs(1), None);
+    let deadlines: Vec<_> = backoff.deadlines(start).collect();
+    assert!(deadlines.len() < 100);
+    assert_eq!(deadlines.last(), Some(&None));
+    assert!(deadlines[..deadlines.len() - 1].iter().all(Option::is_some));
+}
+
+#[test]
+fn custom_jitter_rng() {
+    // Always draws the largest number in the range.
+    struct Highest;
+    impl JitterRng for Highest {
+        fn gen_range(&mut self, range: std::ops::Range<u32>) -> u32 {
+            range.end - 1
+        }
+    }
+
+    // Full jitter picks the whole delay, both for delays that fit in a `u32`
+    // of nanoseconds and for longer ones.
+    let mut backoff = Backoff::new(10, Duration::from_millis(100), Duration::from_secs(100));
+    backoff.set_jitter_strategy(JitterStrategy::Full);
+    let durations: Vec<_> = backoff.iter_with_rng(Highest).flatten().collect();
+    let expected: Vec<_> = (0..9).map(|n| backoff.duration_at(n).unwrap()).collect();
+    assert_eq!(durations, expected);
+    assert!(durations[8] > Duration::from_nanos(u32::MAX as u64));
+
+    // A seeded generator matches the seeded iterator.
+    let backoff = Backoff::new(10, Duration::from_millis(100), Duration::from_secs(100));
+    let durations: Vec<_> = backoff.iter_with_rng(fastrand::Rng::with_seed(3)).collect();
+    assert_eq!(durations, backoff.iter_seeded(3).collect::<Vec<_>>());
+}
+
+#[test]
+fn borrowed_and_owned_iteration_match() {
+    let mut backoff = Backoff::new(8, Duration::from_millis(100), Duration::from_secs(10));
+    backoff.set_jitter(0.5);
+    let borrowed: Vec<_> = (&backoff).into_iter().with_seed(11).collect();
+    let owned: Vec<_> = backoff.clone().into_iter().with_seed(11).collect();
+    assert_eq!(borrowed, owned);
+    assert_eq!(borrowed, backoff.iter_seeded(11).collect::<Vec<_>>());
+    assert_eq!(borrowed, backoff.iter().with_seed(11).collect::<Vec<_>>());
+}
+
+#[test]
+fn on_retry_runs_before_each_sleep() {
+    let calls = Arc::new(std::sync::Mutex::new(vec![]));
+    let mut backoff = Backoff::new(4, Duration::from_millis(100), None);
+    backoff.set_jitter(0.0);
+    let log = calls.clone();
+    backoff.set_on_retry(move |attempt, delay| log.lock().unwrap().push((attempt, delay)));
+
+    // Peeking doesn't run the callback, yielding does.
+    let mut iter = backoff.iter();
+    iter.peek_next();
+    assert!(calls.lock().unwrap().is_empty());
+    assert_eq!(iter.by_ref().count(), 4);
+    let expected = [(0, 100), (1, 200), (2, 400)].map(|(n, d)| (n, Duration::from_millis(d)));
+    assert_eq!(*calls.lock().unwrap(), expected);
+
+    // Clones share the callback.
+    let clone = backoff.clone();
+    assert_eq!(clone, backoff);
+    clone.iter().next();
+    assert_eq!(calls.lock().unwrap().len(), 4);
+
+    // Delays replaced with `None` don't run the callback.
+    let count = |iter: &mut dyn Iterator<Item = Option<Duration>>| {
+        calls.lock().unwrap().clear();
+        let yielded = iter.flatten().count();
+        (yielded, calls.lock().unwrap().len())
+    };
+    assert_eq!(
+        count(&mut backoff.clone().into_iter_capped_attempts(3)),
+        (2, 2)
+    );
+    let limit = Duration::from_millis(250);
+    assert_eq!(count(&mut backoff.iter().take_while_under(limit)), (2, 2));
+
+    // Nor do delays which are never slept on.
+    calls.lock().unwrap().clear();
+    backoff.set_min(Duration::from_millis(1));
+    backoff.compare_jitter(100);
+    backoff.iter().drain_sleeping();
+    assert!(calls.lock().unwrap().is_empty());
+}
+
+#[test]
+fn capped_delays() {
+    let max = Duration::from_millis(500);
+    let mut backoff = Backoff::new(6, Duration::from_millis(100), max);
+    backoff.set_jitter(0.0);
+    let mut iter = backoff.iter();
+    assert!(!iter.is_saturated());
+
+    // 100ms, 200ms and 400ms fit, 800ms and 1.6s are capped.
+    let delays: Vec<_> = iter.clone().with_capped().collect();
+    let capped: Vec<_> = delays.iter().flatten().map(|delay| delay.capped).collect();
+    assert_eq!(capped, [false, false, false, true, true]);
+    assert_eq!(delays[3].unwrap().duration, max);
+    assert_eq!(delays[5], None);
+
+    iter.nth(2);
+    assert!(!iter.is_saturated());
+    iter.next();
+    assert!(iter.is_saturated());
+
+    // Reaching the max exactly saturates without capping.
+    let mut backoff = Backoff::new(4, Duration::from_millis(100), Duration::from_millis(200));
+    backoff.set_jitter(0.0);
+    let mut iter = backoff.iter();
+    iter.nth(1);
+    assert!(iter.is_saturated());
+    let capped: Vec<_> = backoff
+        .iter()
+        .with_capped()
+        .flatten()
+        .map(|d| d.capped)
+        .collect();
+    assert_eq!(capped, [false, false, true]);
+
+    // Jitter pushing a delay over the max caps it too.
+    let max = Duration::from_secs(1);
+    let mut backoff = Backoff::new(20, Duration::from_millis(900), max);
+    backoff.set_factor(1);
+    backoff.set_jitter_bias(1.0);
+    let delays: Vec<_> = backoff.iter_seeded(7).with_capped().flatten().collect();
+    assert!(delays.iter().any(|delay| delay.capped));
+    assert!(delays
+        .iter()
+        .all(|delay| delay.capped == (delay.duration == max)));
+}
+
+#[test]
+fn skip_to_attempt() {
+    let mut backoff = Backoff::new(10, Duration::from_millis(100), None);
+    backoff.set_jitter(0.0);
+    let mut iter = backoff.iter();
+    iter.skip_to(3);
+    assert_eq!(iter.attempt(), 3);
+    assert_eq!(iter.next(), Some(backoff.duration_at(3)));
+    assert_eq!(iter.len(), 6);
+
+    // `nth` skips the same way, and agrees with stepping one at a time.
+    let mut iter = backoff.iter();
+    assert_eq!(iter.nth(4), Some(backoff.duration_at(4)));
+    assert_eq!(iter.nth(1), Some(backoff.duration_at(6)));
+    assert_eq!(iter.nth(2), Some(None));
+    assert_eq!(backoff.iter().nth(9), backoff.iter().last());
+
+    // A peeked item is the first one `nth` skips.
+    let mut iter = backoff.iter();
+    iter.peek_next();
+    assert_eq!(iter.nth(1), Some(backoff.duration_at(1)));
+
+    // Settings which look at the previous delay walk the schedule instead.
+    let mut configs = vec![];
+    let mut smoothed = Backoff::new(20, Duration::from_millis(100), None);
+    smoothed.set_jitter(0.0);
+    smoothed.set_smoothing(0.5);
+    configs.push(smoothed);
+    let mut floored = Backoff::new(20, Duration::from_millis(100), None);
+    floored.set_floor(Floor::Previous);
+    configs.push(floored);
+    let mut decorrelated = Backoff::new(20, Duration::from_millis(100), None);
+    decorrelated.set_jitter_strategy(JitterStrategy::Decorrelated);
+    configs.push(decorrelated);
+    for backoff in configs {
+        let mut walked = backoff.iter_seeded(7);
+        for _ in 0..10 {
+            walked.next();
+        }
+        assert_eq!(backoff.iter_seeded(7).nth(10), walked.next());
+    }
+
+    // Skipping past the end leaves the iterator exhausted.
+    let mut iter = backoff.iter();
+    iter.skip_to(20);
+    assert_eq!(iter.next(), None);
+    assert_eq!(backoff.iter().nth(usize::MAX), None);
+}
+
+#[test]
+fn first_retry_delay_bypasses_min() {
+    let min = Duration::from_millis(100);
+    let mut backoff = Backoff::new(5, min, None);
+    backoff.set_first_retry_delay(Duration::ZERO);
+    let mut iter = backoff.iter();
+    assert_eq!(iter.next(), Some(Some(Duration::ZERO)));
+
+    // The curve starts from `min` on the second retry.
+    backoff.set_jitter(0.0);
+    let delays: Vec<_> = backoff.iter().collect();
+    assert_eq!(
+        delays,
+        [
+            Some(Duration::ZERO),
+            Some(min),
+            Some(min * 2),
+            Some(min * 4),
+            None
+        ]
+    );
+
+    backoff.set_first_retry_delay(Duration::from_millis(10));
+    assert_eq!(backoff.iter().next(), Some(Some(Duration::from_millis(10))));
+    assert_ne!(backoff, Backoff::new(5, min, None));
+}